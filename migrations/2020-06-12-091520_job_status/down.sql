@@ -0,0 +1,7 @@
+Alter Table job
+    Drop Column status,
+    Drop Column error,
+    Drop Column created,
+    Drop Column updated,
+    Drop Column repo_id,
+    Drop Column package_id;
@@ -0,0 +1,9 @@
+Alter Table job
+    Add Column status     Varchar(255) Not Null Default 'pending',
+    Add Column error      Text,
+    Add Column created    Timestamp    Not Null Default current_timestamp,
+    Add Column updated    Timestamp    Not Null Default current_timestamp,
+    Add Column repo_id    Integer References repo On Delete Cascade,
+    Add Column package_id Integer References package On Delete Cascade;
+
+Update job Set status = 'running' Where worker Is Not Null;
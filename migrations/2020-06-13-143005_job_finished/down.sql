@@ -0,0 +1,6 @@
+Delete From job Where status = 'finished';
+
+Alter Table job
+    Drop Constraint job_package_id_fkey,
+    Add Constraint job_package_id_fkey
+        Foreign Key (package_id) References package On Delete Cascade;
@@ -0,0 +1,6 @@
+-- Finished jobs are kept so that clients can query their outcome, which means
+-- that removing a package must not remove the job that removed it.
+Alter Table job
+    Drop Constraint job_package_id_fkey,
+    Add Constraint job_package_id_fkey
+        Foreign Key (package_id) References package On Delete Set Null;
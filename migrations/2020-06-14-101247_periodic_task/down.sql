@@ -0,0 +1 @@
+Drop Table periodic_task;
@@ -0,0 +1,8 @@
+Create Table periodic_task
+(
+    id       Serial Primary Key,
+    name     Varchar(255) Not Null Unique,
+    period   Integer      Not Null,
+    last_run Timestamp,
+    next_run Timestamp    Not Null Default current_timestamp
+);
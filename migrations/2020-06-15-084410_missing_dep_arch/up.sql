@@ -0,0 +1,5 @@
+Alter Table missing_dep
+    Add Column arch Varchar(255) Not Null Default 'x86_64';
+
+Alter Table missing_dep
+    Alter Column arch Drop Default;
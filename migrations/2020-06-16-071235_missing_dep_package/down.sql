@@ -0,0 +1,2 @@
+Alter Table missing_dep
+    Drop Column package_id;
@@ -0,0 +1,6 @@
+-- The existing entries cannot be attributed to a package. They are recomputed
+-- by the next dependency check.
+Delete From missing_dep;
+
+Alter Table missing_dep
+    Add Column package_id Integer Not Null References package On Delete Cascade;
@@ -0,0 +1 @@
+Drop Table repo_layer;
@@ -0,0 +1,8 @@
+Create Table repo_layer
+(
+    id           Serial Primary Key,
+    repo_id      Integer Not Null References repo On Delete Cascade,
+    base_repo_id Integer Not Null References repo On Delete Cascade,
+    Unique (repo_id, base_repo_id),
+    Check (repo_id <> base_repo_id)
+);
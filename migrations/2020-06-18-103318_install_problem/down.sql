@@ -0,0 +1 @@
+Drop Table install_problem;
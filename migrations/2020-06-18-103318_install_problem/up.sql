@@ -0,0 +1,9 @@
+Create Table install_problem
+(
+    id         Serial Primary Key,
+    repo_id    Integer      Not Null References repo On Delete Cascade,
+    package_id Integer      Not Null References package On Delete Cascade,
+    arch       Varchar(255) Not Null,
+    kind       Varchar(255) Not Null,
+    message    Text         Not Null
+);
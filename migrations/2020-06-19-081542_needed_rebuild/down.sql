@@ -0,0 +1 @@
+Drop Table needed_rebuild;
@@ -0,0 +1,9 @@
+Create Table needed_rebuild
+(
+    id         Serial Primary Key,
+    repo_id    Integer      Not Null References repo On Delete Cascade,
+    package_id Integer      Not Null References package On Delete Cascade,
+    arch       Varchar(255) Not Null,
+    dependency Varchar(255) Not Null,
+    available  Varchar(255) Not Null
+);
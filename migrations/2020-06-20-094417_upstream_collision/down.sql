@@ -0,0 +1 @@
+Drop Table upstream_collision;
@@ -0,0 +1,9 @@
+Create Table upstream_collision
+(
+    id               Serial Primary Key,
+    repo_id          Integer      Not Null References repo On Delete Cascade,
+    package_id       Integer      Not Null References package On Delete Cascade,
+    arch             Varchar(255) Not Null,
+    upstream_repo    Varchar(255) Not Null,
+    upstream_version Varchar(255) Not Null
+);
@@ -0,0 +1,5 @@
+Update package_provides
+Set provides = package.name
+From package
+Where package_provides.package_id = package.id
+  And package_provides.provides = package.name || '=' || package.version;
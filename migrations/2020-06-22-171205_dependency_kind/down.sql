@@ -0,0 +1,4 @@
+Delete From package_depends Where kind != 'depend';
+Alter Table package_depends Drop Column kind;
+Delete From missing_dep Where kind != 'depend';
+Alter Table missing_dep Drop Column kind;
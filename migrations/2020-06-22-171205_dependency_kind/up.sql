@@ -0,0 +1,4 @@
+Alter Table package_depends
+    Add Column kind Varchar(255) Not Null Default 'depend';
+Alter Table missing_dep
+    Add Column kind Varchar(255) Not Null Default 'depend';
@@ -0,0 +1,5 @@
+Alter Table aur_version
+    Drop Column maintainer,
+    Drop Column out_of_date,
+    Drop Column last_modified,
+    Drop Column last_checked;
@@ -0,0 +1,5 @@
+Alter Table aur_version
+    Add Column maintainer    Varchar(255) Null,
+    Add Column out_of_date   Timestamp    Null,
+    Add Column last_modified Timestamp    Null,
+    Add Column last_checked  Timestamp    Not Null Default now();
@@ -0,0 +1 @@
+Drop Table version_source;
@@ -0,0 +1,13 @@
+Create Table version_source
+(
+    id           Serial Primary Key,
+    repo_id      Integer      Not Null References repo On Delete Cascade,
+    package      Varchar(255) Not Null,
+    kind         Varchar(255) Not Null,
+    source       Varchar      Not Null,
+    pattern      Varchar      Null,
+    version      Varchar(255) Null,
+    error        Varchar      Null,
+    last_checked Timestamp    Null,
+    Unique (repo_id, package)
+);
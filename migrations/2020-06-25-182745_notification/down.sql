@@ -0,0 +1,2 @@
+Drop Table notification;
+Drop Table notification_preference;
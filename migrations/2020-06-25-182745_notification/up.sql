@@ -0,0 +1,18 @@
+Create Table notification_preference
+(
+    account_id   Integer      Primary Key References account On Delete Cascade,
+    email        Varchar(255) Not Null,
+    outdated     Boolean      Not Null Default True,
+    missing_deps Boolean      Not Null Default True,
+    failed_jobs  Boolean      Not Null Default True
+);
+
+Create Table notification
+(
+    id         Serial       Primary Key,
+    account_id Integer      Not Null References account On Delete Cascade,
+    kind       Varchar(255) Not Null,
+    message    Text         Not Null,
+    created    Timestamp    Not Null Default now(),
+    sent       Timestamp    Null
+);
@@ -0,0 +1,4 @@
+Drop Table webhook_delivery;
+Drop Table webhook;
+Alter Table job
+    Drop Column run_after;
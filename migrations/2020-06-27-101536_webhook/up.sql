@@ -0,0 +1,27 @@
+-- Jobs that failed for a reason that may go away, like a delivery to a server
+-- that is down, are retried after a delay.
+Alter Table job
+    Add Column run_after Timestamp Null;
+
+Create Table webhook
+(
+    id      Serial       Primary Key,
+    repo_id Integer      Not Null References repo On Delete Cascade,
+    url     Varchar      Not Null,
+    secret  Varchar(255) Not Null,
+    created Timestamp    Not Null Default now()
+);
+
+Create Table webhook_delivery
+(
+    id           Serial       Primary Key,
+    webhook_id   Integer      Not Null References webhook On Delete Cascade,
+    event        Varchar(255) Not Null,
+    payload      Jsonb        Not Null,
+    attempts     Integer      Not Null Default 0,
+    status_code  Integer      Null,
+    error        Text         Null,
+    created      Timestamp    Not Null Default now(),
+    last_attempt Timestamp    Null,
+    delivered    Timestamp    Null
+);
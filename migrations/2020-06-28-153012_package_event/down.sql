@@ -0,0 +1,3 @@
+Drop Table package_event;
+Alter Table package
+    Drop Column description;
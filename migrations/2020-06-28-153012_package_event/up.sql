@@ -0,0 +1,21 @@
+Alter Table package
+    Add Column description Varchar Null;
+
+-- Packages are deleted once they are removed from their repository, so what
+-- happened to them is recorded separately.
+Create Table package_event
+(
+    id          Serial       Primary Key,
+    repo_id     Integer      Not Null References repo On Delete Cascade,
+    kind        Varchar(255) Not Null,
+    name        Varchar(255) Not Null,
+    version     Varchar(255) Not Null,
+    arch        Varchar(255) Not Null,
+    description Varchar      Null,
+    created     Timestamp    Not Null Default now()
+);
+
+Insert Into package_event (repo_id, kind, name, version, arch, created)
+Select repo_id, 'uploaded', name, version, arch, created
+From package
+Where Not deleted;
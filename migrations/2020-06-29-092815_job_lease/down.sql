@@ -0,0 +1,2 @@
+Alter Table job
+    Drop Column lease_until;
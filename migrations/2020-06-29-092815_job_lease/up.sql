@@ -0,0 +1,4 @@
+Alter Table job
+    Add Column lease_until Timestamp;
+
+Update job Set lease_until = current_timestamp Where status = 'running';
@@ -0,0 +1,4 @@
+Alter Table notification_preference
+    Drop Column confirmed,
+    Drop Column confirmation_token,
+    Drop Column confirmation_sent;
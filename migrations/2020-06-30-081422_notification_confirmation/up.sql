@@ -0,0 +1,4 @@
+Alter Table notification_preference
+    Add Column confirmed          Boolean      Not Null Default False,
+    Add Column confirmation_token Varchar(255) Null,
+    Add Column confirmation_sent  Timestamp    Null;
@@ -0,0 +1,139 @@
+(function () {
+    const svgNs = "http://www.w3.org/2000/svg";
+    const nodeWidth = 180;
+    const nodeHeight = 36;
+    const columnGap = 80;
+    const rowGap = 14;
+
+    function svg(tag, attributes) {
+        const element = document.createElementNS(svgNs, tag);
+        for (const [key, value] of Object.entries(attributes)) {
+            element.setAttribute(key, value);
+        }
+        return element;
+    }
+
+    function truncate(text, length) {
+        return text.length > length ? text.substring(0, length - 1) + "…" : text;
+    }
+
+    // Packages of the repository are placed in the column of their build
+    // stage, everything they depend on from elsewhere goes into the first
+    // column.
+    function layout(graph) {
+        const columns = [[]];
+        const stageOf = {};
+        graph.build_order.forEach((stage, i) => {
+            for (const id of stage) {
+                stageOf[id] = i + 1;
+            }
+            columns.push([]);
+        });
+        for (const node of graph.nodes) {
+            columns[stageOf[node.id] || 0].push(node);
+        }
+        const nonEmpty = columns.filter(column => column.length > 0);
+
+        const positions = {};
+        nonEmpty.forEach((column, x) => {
+            column.sort((a, b) => a.name.localeCompare(b.name));
+            column.forEach((node, y) => {
+                positions[node.id] = {
+                    x: x * (nodeWidth + columnGap),
+                    y: y * (nodeHeight + rowGap)
+                };
+            });
+        });
+        const width = nonEmpty.length * (nodeWidth + columnGap) - columnGap;
+        const height = Math.max(...nonEmpty.map(c => c.length)) * (nodeHeight + rowGap) - rowGap;
+        return { positions, width: Math.max(width, 0), height: Math.max(height, 0) };
+    }
+
+    function render(container, graph) {
+        if (graph.nodes.length === 0) {
+            container.textContent = "This repository has no active packages.";
+            return;
+        }
+
+        const { positions, width, height } = layout(graph);
+        const root = svg("svg", { width: width + 2, height: height + 2, viewBox: `-1 -1 ${width + 2} ${height + 2}` });
+
+        const edges = graph.edges.map(edge => {
+            const from = positions[edge.from];
+            const to = positions[edge.to];
+            const x1 = from.x;
+            const y1 = from.y + nodeHeight / 2;
+            const x2 = to.x + nodeWidth;
+            const y2 = to.y + nodeHeight / 2;
+            const dx = Math.max(Math.abs(x1 - x2) / 2, 40);
+            const path = svg("path", {
+                d: `M ${x1} ${y1} C ${x1 - dx} ${y1}, ${x2 + dx} ${y2}, ${x2} ${y2}`,
+                class: "graph-edge"
+            });
+            const title = svg("title", {});
+            title.textContent = edge.dependency;
+            path.appendChild(title);
+            root.appendChild(path);
+            return { edge, path };
+        });
+
+        const nodes = graph.nodes.map(node => {
+            const { x, y } = positions[node.id];
+            const group = svg("g", {
+                class: `graph-node graph-node-${node.kind}` + (node.leaf ? " graph-leaf" : ""),
+                transform: `translate(${x}, ${y})`
+            });
+            group.appendChild(svg("rect", { width: nodeWidth, height: nodeHeight, rx: 4 }));
+            const name = svg("text", { x: 8, y: 15 });
+            name.textContent = truncate(node.name, 24);
+            group.appendChild(name);
+            if (node.version) {
+                const version = svg("text", { x: 8, y: 29, class: "graph-version" });
+                version.textContent = truncate(`${node.version} (${node.arch})`, 28);
+                group.appendChild(version);
+            }
+            const title = svg("title", {});
+            title.textContent = node.version ? `${node.name} ${node.version} (${node.kind})` : `${node.name} (${node.kind})`;
+            group.appendChild(title);
+            root.appendChild(group);
+            return { node, group };
+        });
+
+        let selected = null;
+        for (const { node, group } of nodes) {
+            group.addEventListener("click", _ => {
+                selected = selected === node.id ? null : node.id;
+                const related = new Set([selected]);
+                for (const { edge, path } of edges) {
+                    const active = edge.from === selected || edge.to === selected;
+                    path.classList.toggle("graph-active", active);
+                    if (active) {
+                        related.add(edge.from);
+                        related.add(edge.to);
+                    }
+                }
+                for (const { node, group } of nodes) {
+                    group.classList.toggle("graph-dimmed", selected !== null && !related.has(node.id));
+                }
+            });
+        }
+
+        container.appendChild(root);
+    }
+
+    window.addEventListener("DOMContentLoaded", _ => {
+        const container = document.getElementById("dependency-graph");
+        if (!container) {
+            return;
+        }
+        fetch(container.dataset.src, { headers: { "Accept": "application/json" } })
+            .then(response => {
+                if (!response.ok) {
+                    throw new Error(response.statusText);
+                }
+                return response.json();
+            })
+            .then(graph => render(container, graph))
+            .catch(error => container.textContent = `Failed to load the dependency graph: ${error.message}`);
+    });
+})();
@@ -1,29 +1,141 @@
 use std::fs;
 use std::io;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use std::path::PathBuf;
 
 use alpm::Alpm;
 use alpm::SigLevel;
 use fehler::throws;
 
+use crate::config::UpstreamRepo;
+use crate::config::Verify;
 use crate::error::Error;
 
+/// Downloads the upstream databases of the architecture and verifies their
+/// signatures with the keys in `keyring`. `pacman-db/<arch>` is a symlink to
+/// one of two directories, `<arch>.a` and `<arch>.b`. The databases are
+/// downloaded into the other one, and the symlink is only switched over to it,
+/// in a single rename, once every database could be downloaded and verified.
+/// That way, readers never see a missing or partially synced directory.
 #[throws]
-pub fn sync(arch: &str, mirror: impl Fn(&str, &str) -> String) {
-    let mut alpm = create(arch)?;
+pub fn sync(arch: &str, repos: &[UpstreamRepo], keyring: &str) {
+    let path = format!("pacman-db/{}", arch);
+    let current = current_db_dir(&path, arch)?;
+    let (a, b) = (format!("{}.a", arch), format!("{}.b", arch));
+    let next = if current.as_ref() == Some(&a) { b } else { a };
+
+    let next_path = format!("pacman-db/{}", next);
+    if Path::new(&next_path).exists() {
+        fs::remove_dir_all(&next_path)?;
+    }
+    fs::create_dir_all(&next_path)?;
+
+    if let Err(error) = download(&next_path, arch, repos, keyring) {
+        fs::remove_dir_all(&next_path)?;
+        Err(error)?
+    }
+
+    // The previous directory is kept until the next sync, since readers may
+    // still be using it.
+    let link_path = format!("pacman-db/{}.link", arch);
+    if fs::symlink_metadata(&link_path).is_ok() {
+        fs::remove_file(&link_path)?;
+    }
+    symlink(&next, &link_path)?;
+    fs::rename(&link_path, &path)?;
+}
+
+/// Returns the directory that the symlink at `path` points to. A directory
+/// at `path`, as created before the symlink was introduced, is moved to
+/// `<arch>.a` and replaced by a symlink to it.
+#[throws(io::Error)]
+fn current_db_dir(path: &str, arch: &str) -> Option<String> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            Some(fs::read_link(path)?.to_string_lossy().into_owned())
+        }
+        Ok(_) => {
+            let dir = format!("{}.a", arch);
+            fs::rename(path, format!("pacman-db/{}", dir))?;
+            symlink(&dir, path)?;
+            Some(dir)
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => Err(error)?
+    }
+}
+
+#[throws]
+fn download(db_path: &str, arch: &str, repos: &[UpstreamRepo], keyring: &str) {
+    let mut alpm = Alpm::new("/", db_path)?;
+    alpm.set_gpgdir(keyring)?;
+    for repo in repos {
+        alpm.register_syncdb(repo.name.as_str(), sig_level(repo.verify))?;
+    }
     for mut db in alpm.syncdbs_mut() {
-        let server_url = mirror(db.name(), arch);
-        db.add_server(server_url)?;
-        db.update(false)?;
+        let name = db.name().to_owned();
+        if let Some(repo) = repos.iter().find(|r| r.name == name) {
+            for server_url in repo.server_urls(arch) {
+                db.add_server(server_url)?;
+            }
+        }
+        db.update(false)
+            .map_err(|e| format!("Failed to sync {} ({}): {}", name, arch, e))?;
     }
 }
 
+fn sig_level(verify: Verify) -> SigLevel {
+    match verify {
+        Verify::Required => SigLevel::DATABASE,
+        Verify::Optional => SigLevel::DATABASE | SigLevel::DATABASE_OPTIONAL,
+        Verify::Never => SigLevel::NONE,
+    }
+}
+
+/// Opens the synced upstream databases of the architecture. Their signatures
+/// were already checked when they were synced.
 #[throws]
-pub fn create(arch: &str) -> Alpm {
+pub fn create(arch: &str, repos: &[UpstreamRepo]) -> Alpm {
     let db_path = db_path(arch)?;
     let alpm = Alpm::new("/", &db_path)?;
-    alpm.register_syncdb("core", SigLevel::NONE)?;
-    alpm.register_syncdb("community", SigLevel::NONE)?;
-    alpm.register_syncdb("extra", SigLevel::NONE)?;
+    for repo in repos {
+        alpm.register_syncdb(repo.name.as_str(), SigLevel::NONE)?;
+    }
+    alpm
+}
+
+/// Opens the synced upstream databases together with additional databases in
+/// a fresh database directory `dir`. The local database is empty, so that
+/// installing packages can be simulated as on a new system.
+#[throws]
+pub fn create_simulation(dir: &Path, arch: &str, repos: &[UpstreamRepo], extra: &[(String, PathBuf)]) -> Alpm {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    let sync_path = dir.join("sync");
+    fs::create_dir_all(&sync_path)?;
+
+    let upstream_sync_path = fs::canonicalize(db_path(arch)?)?.join("sync");
+    for repo in repos {
+        let db = upstream_sync_path.join(format!("{}.db", repo.name));
+        if db.exists() {
+            symlink(&db, sync_path.join(format!("{}.db", repo.name)))?;
+        }
+    }
+    for (name, db) in extra {
+        symlink(fs::canonicalize(db)?, sync_path.join(format!("{}.db", name)))?;
+    }
+
+    let db_path = dir.to_str()
+        .ok_or_else(|| format!("Invalid database directory {}", dir.display()))?;
+    let alpm = Alpm::new("/", db_path)?;
+    for repo in repos {
+        alpm.register_syncdb(repo.name.as_str(), SigLevel::NONE)?;
+    }
+    for (name, _) in extra {
+        alpm.register_syncdb(name.as_str(), SigLevel::NONE)?;
+    }
     alpm
 }
 
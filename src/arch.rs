@@ -0,0 +1,34 @@
+use diesel::PgConnection;
+use fehler::throws;
+
+use crate::db::get_archs_by_repo;
+use crate::error::Error;
+
+/// The architecture that a repository is checked against if it only has `any`
+/// packages.
+pub const DEFAULT_ARCH: &str = "x86_64";
+
+/// Whether a package for `provider` can satisfy a dependency of a package for
+/// `dependent`.
+pub fn arch_matches(provider: &str, dependent: &str) -> bool {
+    provider == dependent || provider == "any" || dependent == "any"
+}
+
+/// The architectures that the repository is checked for: those of its active
+/// packages, or `DEFAULT_ARCH` if it only has `any` packages.
+#[throws]
+pub fn repo_archs(conn: &PgConnection, repo_id: i32) -> Vec<String> {
+    let mut archs = get_archs_by_repo(conn, repo_id)?;
+    if archs.is_empty() {
+        archs.push(DEFAULT_ARCH.to_owned());
+    }
+    archs
+}
+
+#[test]
+fn test_arch_matches() {
+    assert!(arch_matches("x86_64", "x86_64"));
+    assert!(arch_matches("any", "aarch64"));
+    assert!(arch_matches("aarch64", "any"));
+    assert!(!arch_matches("x86_64", "aarch64"));
+}
@@ -1,25 +1,56 @@
+use std::cmp::min;
+use std::thread;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
 use fehler::throws;
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
 use serde::Deserialize;
 
+use crate::config::Aur;
 use crate::error::Error;
 
 #[derive(Debug, Deserialize)]
 struct Response<T> {
+    #[serde(rename(deserialize = "type"))]
+    kind: String,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
     results: Vec<T>
 }
 
 #[derive(Debug, Deserialize)]
 struct Info {
-    #[serde(rename(deserialize = "ID"))]
-    id: u32,
     #[serde(rename(deserialize = "Name"))]
     name: String,
     #[serde(rename(deserialize = "Version"))]
     version: String,
+    #[serde(rename(deserialize = "Maintainer"))]
+    maintainer: Option<String>,
+    #[serde(rename(deserialize = "OutOfDate"))]
+    out_of_date: Option<i64>,
+    #[serde(rename(deserialize = "LastModified"))]
+    last_modified: i64,
+}
+
+/// A package as known to the AUR.
+#[derive(Debug)]
+pub struct AurInfo {
+    pub name: String,
+    pub version: String,
+    /// Orphaned packages have no maintainer.
+    pub maintainer: Option<String>,
+    /// When the package was flagged out of date, if it is.
+    pub out_of_date: Option<NaiveDateTime>,
+    pub last_modified: NaiveDateTime,
 }
 
+/// Looks up the packages in the AUR. Packages that are not in the AUR are
+/// missing from the result.
 #[throws]
-pub fn check_aur_version(packages: &[&str]) -> Vec<(String, String)> {
+pub fn get_aur_info(aur: &Aur, packages: &[&str]) -> Vec<AurInfo> {
     let client = reqwest::blocking::Client::new();
 
     let mut query = vec![("v", "5"), ("type", "info")];
@@ -27,16 +58,102 @@ pub fn check_aur_version(packages: &[&str]) -> Vec<(String, String)> {
         query.push(("arg[]", package));
     }
 
-    let response = client
-        .get("https://aur.archlinux.org/rpc.php")
-        .query(&query)
-        .send()?;
+    let mut attempt = 0;
+    let response = loop {
+        let response = client
+            .get(&aur.rpc_url)
+            .query(&query)
+            .send()?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            break response;
+        }
+        if attempt == RATE_LIMIT_RETRIES {
+            Err("The AUR rate limit was reached")?
+        }
+        let retry_after = response.headers().get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok());
+        let delay = retry_delay(retry_after, attempt);
+        println!("The AUR rate limit was reached, retrying in {} seconds.", delay.as_secs());
+        thread::sleep(delay);
+        attempt += 1;
+    };
     if !response.status().is_success() {
         Err(format!("response failed: {}", response.status()))?
     }
 
+    // Errors, like a rate limit or too many arguments, are reported in the
+    // body as well.
     let response: Response<Info> = response.json()?;
+    if response.kind == "error" {
+        let error = response.error.unwrap_or_else(|| "unknown error".to_owned());
+        Err(format!("The AUR returned an error: {}", error))?
+    }
+
     response.results.into_iter()
-        .map(|p| (p.name, p.version))
+        .map(|p| AurInfo {
+            name: p.name,
+            version: p.version,
+            maintainer: p.maintainer,
+            out_of_date: p.out_of_date.map(|t| NaiveDateTime::from_timestamp(t, 0)),
+            last_modified: NaiveDateTime::from_timestamp(p.last_modified, 0)
+        })
         .collect()
 }
+
+/// How often a request that was rejected by the rate limit is retried.
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// The longest time that is waited before retrying a request.
+const MAX_RETRY_DELAY: u64 = 10 * 60;
+
+/// How long to wait before retrying a request that was rejected by the rate
+/// limit: as long as `Retry-After` asks for, if it is given in seconds, and
+/// otherwise one minute, doubled with every attempt.
+fn retry_delay(retry_after: Option<&str>, attempt: u32) -> Duration {
+    let delay = match retry_after.and_then(|value| value.trim().parse::<u64>().ok()) {
+        Some(delay) => delay,
+        None => 60 << attempt
+    };
+    Duration::from_secs(min(delay, MAX_RETRY_DELAY))
+}
+
+/// Splits the packages into batches that can be looked up with a single
+/// request. A batch has at most `batch_size` packages, and its arguments take
+/// at most `max_query_len` bytes, since the AUR rejects overlong URLs.
+pub fn batches<'a>(packages: &[&'a str], batch_size: usize, max_query_len: usize) -> Vec<Vec<&'a str>> {
+    let mut batches = Vec::new();
+    let mut batch = Vec::new();
+    let mut query_len = 0;
+    for package in packages {
+        // Every package is sent as `&arg[]=<package>`, where the brackets are
+        // percent-encoded.
+        let len = package.len() + "&arg%5B%5D=".len();
+        if !batch.is_empty() && (batch.len() >= batch_size || query_len + len > max_query_len) {
+            batches.push(batch);
+            batch = Vec::new();
+            query_len = 0;
+        }
+        batch.push(*package);
+        query_len += len;
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    batches
+}
+
+#[test]
+fn test_batches() {
+    let packages = ["a", "bb", "ccc", "dddd", "eeeee"];
+    assert_eq!(batches(&packages, 2, 1000), vec![vec!["a", "bb"], vec!["ccc", "dddd"], vec!["eeeee"]]);
+    assert_eq!(batches(&packages, 10, 26), vec![vec!["a", "bb"], vec!["ccc"], vec!["dddd"], vec!["eeeee"]]);
+    assert_eq!(batches(&packages[..0], 10, 1000), Vec::<Vec<&str>>::new());
+}
+
+#[test]
+fn test_retry_delay() {
+    assert_eq!(retry_delay(Some("30"), 0), Duration::from_secs(30));
+    assert_eq!(retry_delay(Some("86400"), 0), Duration::from_secs(MAX_RETRY_DELAY));
+    assert_eq!(retry_delay(None, 0), Duration::from_secs(60));
+    assert_eq!(retry_delay(Some("Wed, 21 Oct 2015 07:28:00 GMT"), 2), Duration::from_secs(240));
+}
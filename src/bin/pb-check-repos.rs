@@ -0,0 +1,63 @@
+use std::env;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use anyhow::Error;
+use diesel::PgConnection;
+use fehler::throws;
+
+use pacman::connect_db;
+use pacman::consistency::check_repo;
+use pacman::consistency::unreferenced_files;
+use pacman::db::get_repos;
+use pacman::jobs::create_repo_rebuild;
+
+/// Reports repositories whose database does not match the active packages.
+///
+/// With `--rebuild`, a rebuild of every repository that can be fixed by one
+/// is enqueued. Repositories can also be rebuilt explicitly with
+/// `--rebuild <repo id>...`.
+#[throws]
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let rebuild = args.first().map_or(false, |a| a == "--rebuild");
+    let explicit = args.iter().skip(1)
+        .map(|a| a.parse::<i32>().map_err(|_| anyhow!("Invalid repo id {}", a)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !args.is_empty() && !rebuild {
+        Err(anyhow!("Usage: pb-check-repos [--rebuild [<repo id>...]]"))?
+    }
+
+    let conn = &connect_db()?;
+
+    if explicit.is_empty() {
+        check_all(conn, rebuild)?;
+    } else {
+        for repo_id in explicit {
+            enqueue_rebuild(conn, repo_id)?;
+        }
+    }
+}
+
+#[throws]
+fn check_all(conn: &PgConnection, rebuild: bool) {
+    for repo in get_repos(conn)? {
+        let drift = check_repo(conn, repo.id)?;
+        for drift in &drift {
+            println!("repo {} ({}): {}", repo.name, repo.id, drift);
+        }
+        if rebuild && drift.iter().any(|d| d.fixed_by_rebuild()) {
+            enqueue_rebuild(conn, repo.id)?;
+        }
+    }
+
+    for drift in unreferenced_files(conn, Duration::from_secs(60 * 60))? {
+        println!("{}", drift);
+    }
+}
+
+#[throws]
+fn enqueue_rebuild(conn: &PgConnection, repo_id: i32) {
+    let job = create_repo_rebuild(conn, repo_id)?;
+    println!("Enqueued rebuild of repo {} as job {}.", repo_id, job.id);
+}
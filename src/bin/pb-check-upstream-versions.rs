@@ -0,0 +1,11 @@
+use anyhow::Error;
+use fehler::throws;
+
+use pacman::connect_db;
+use pacman::maintenance::check_upstream_versions;
+
+#[throws]
+fn main() {
+    let conn = &connect_db()?;
+    check_upstream_versions(conn)?;
+}
@@ -1,37 +1,13 @@
 use anyhow::Error;
 use fehler::throws;
 
-use pacman::alpm;
+use pacman::config::Settings;
 use pacman::connect_db;
-use pacman::db::get_repos;
-use pacman::jobs::create_check_deps;
+use pacman::maintenance::sync_pacman_dbs;
 
 #[throws]
 fn main() {
     let conn = &connect_db()?;
-
-    alpm::sync("x86_64", x86_64_mirror)?;
-    println!("Synced x86_64 repositories.");
-
-    for arch in &["aarch64", "arm", "armv6h", "armv7h"] {
-        alpm::sync(arch, arm_mirror)?;
-        println!("Synced {} repositories.", arch);
-    }
-
-    let repos = get_repos(conn)?;
-    for repo in repos {
-        create_check_deps(conn, repo.id)?;
-    }
-}
-
-fn x86_64_mirror(repo: &str, arch: &str) -> String {
-    format!(
-        "http://archlinux.honkgong.info/{}/os/{}",
-        repo, arch)
-}
-
-fn arm_mirror(repo: &str, arch: &str) -> String {
-    format!(
-        "http://mirror.archlinuxarm.org/{}/{}",
-        arch, repo)
+    let settings = Settings::load()?;
+    sync_pacman_dbs(conn, &settings.upstream, &settings.keyring)?;
 }
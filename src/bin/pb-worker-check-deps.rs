@@ -1,30 +1,129 @@
-#![feature(never_type)]
-
-use std::thread;
 use std::time::Duration;
 
 use anyhow::Error;
+use diesel::PgConnection;
+use fehler::throws;
 
+use pacman::collision::upstream_collisions;
+use pacman::config::Settings;
 use pacman::connect_db;
-use pacman::db::delete_job;
+use pacman::db::fail_job;
+use pacman::db::finish_job;
+use pacman::db::get_missing_deps;
+use pacman::db::models::NewInstallProblem;
+use pacman::db::models::NewMissingDep;
+use pacman::db::models::NewNeededRebuild;
+use pacman::db::models::NewUpstreamCollision;
+use pacman::db::models::WebhookEvent;
+use pacman::db::replace_install_problems;
 use pacman::db::replace_missing_deps;
+use pacman::db::replace_needed_rebuilds;
+use pacman::db::replace_upstream_collisions;
+use pacman::jobs::CheckDeps;
+use pacman::installability::check_installability;
 use pacman::jobs::get_check_deps;
 use pacman::missing::missing_dependencies;
+use pacman::missing::MissingDepsChange;
+use pacman::notification::notify_failed_job;
+use pacman::notification::notify_missing_deps;
+use pacman::rebuild::needed_rebuilds;
+use pacman::syncdb::UpstreamDbs;
+use pacman::webhook::trigger_webhooks;
+use pacman::worker::release_stale_jobs;
+use pacman::worker::start_heartbeat;
+use pacman::worker::worker_id;
+use pacman::worker::Shutdown;
 
-fn main() -> Result<!, Error> {
+#[throws]
+fn main() {
+    let shutdown = Shutdown::install()?;
     let conn = &connect_db()?;
+    let settings = Settings::load()?;
+    let upstream = UpstreamDbs::new(settings.upstream);
+    let worker = worker_id();
+    start_heartbeat(&worker)?;
 
-    loop {
-        if let Some((id, check_deps)) = get_check_deps(conn, "worker")? {
-            let repo_id = check_deps.repo_id;
-            let missing_deps = missing_dependencies(conn, repo_id)?.into_iter()
-                .map(|m| m.to_string())
-                .collect::<Vec<_>>();
-            println!("repo {} is missing these dependencies: {:?}", repo_id, missing_deps);
-            replace_missing_deps(conn, repo_id, missing_deps)?;
-            delete_job(conn, id)?;
+    while !shutdown.requested() {
+        release_stale_jobs(conn, &["check_deps"])?;
+        if let Some((id, check_deps)) = get_check_deps(conn, &worker)? {
+            match perform_check_deps(conn, &upstream, check_deps) {
+                Ok(()) => finish_job(conn, id)?,
+                Err(error) => {
+                    println!("Dependency check {} failed: {:?}", id, error);
+                    let error = format!("{:#}", error);
+                    fail_job(conn, id, &error)?;
+                    // The job failed either way, so the worker keeps going.
+                    if let Err(error) = notify_failed_job(conn, id, &error) {
+                        println!("Failed to notify about failed job {}: {:?}", id, error);
+                    }
+                }
+            }
         } else {
-            thread::sleep(Duration::from_secs(10));
+            shutdown.sleep(Duration::from_secs(10));
         }
     }
+
+    println!("Shutting down.");
+}
+
+#[throws]
+fn perform_check_deps(conn: &PgConnection, upstream: &UpstreamDbs, check_deps: CheckDeps) {
+    let repo_id = check_deps.repo_id;
+    let missing = missing_dependencies(conn, repo_id, upstream)?;
+
+    let rebuilds = needed_rebuilds(conn, repo_id, upstream, &missing)?.into_iter()
+        .map(|r| NewNeededRebuild {
+            repo_id,
+            package_id: r.package_id,
+            arch: r.arch,
+            dependency: r.dependency,
+            available: r.available
+        })
+        .collect::<Vec<_>>();
+    println!("repo {} has packages that need a rebuild: {:?}", repo_id, rebuilds);
+    replace_needed_rebuilds(conn, repo_id, rebuilds)?;
+
+    let missing_deps = missing.into_iter()
+        .map(|m| NewMissingDep {
+            repo_id,
+            dependency: m.dependency.to_string(),
+            arch: m.arch,
+            package_id: m.package_id,
+            kind: m.kind
+        })
+        .collect::<Vec<_>>();
+    println!("repo {} is missing these dependencies: {:?}", repo_id, missing_deps);
+    let previous = get_missing_deps(conn, repo_id)?.into_iter()
+        .map(|(m, _)| m)
+        .collect::<Vec<_>>();
+    let change = MissingDepsChange::new(&previous, &missing_deps);
+    if !change.is_empty() {
+        notify_missing_deps(conn, repo_id, &change)?;
+        trigger_webhooks(conn, repo_id, WebhookEvent::MissingDepsChanged, serde_json::to_value(&change)?)?;
+    }
+    replace_missing_deps(conn, repo_id, missing_deps)?;
+
+    let install_problems = check_installability(conn, repo_id, upstream.upstream())?.into_iter()
+        .map(|p| NewInstallProblem {
+            repo_id,
+            package_id: p.package_id,
+            arch: p.arch,
+            kind: p.kind,
+            message: p.message
+        })
+        .collect::<Vec<_>>();
+    println!("repo {} has these installation problems: {:?}", repo_id, install_problems);
+    replace_install_problems(conn, repo_id, install_problems)?;
+
+    let collisions = upstream_collisions(conn, repo_id, upstream)?.into_iter()
+        .map(|c| NewUpstreamCollision {
+            repo_id,
+            package_id: c.package_id,
+            arch: c.arch,
+            upstream_repo: c.upstream_repo,
+            upstream_version: c.upstream_version
+        })
+        .collect::<Vec<_>>();
+    println!("repo {} has these packages that are also upstream: {:?}", repo_id, collisions);
+    replace_upstream_collisions(conn, repo_id, collisions)?;
 }
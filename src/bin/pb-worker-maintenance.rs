@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use anyhow::Error;
+use diesel::PgConnection;
+use fehler::throws;
+
+use pacman::config::Settings;
+use pacman::connect_db;
+use pacman::db::claim_job;
+use pacman::db::fail_job;
+use pacman::db::finish_job;
+use pacman::db::models::Job;
+use pacman::maintenance::run_task;
+use pacman::maintenance::TASKS;
+use pacman::scheduler::run_due_tasks;
+use pacman::worker::release_stale_jobs;
+use pacman::worker::start_heartbeat;
+use pacman::worker::worker_id;
+use pacman::worker::Shutdown;
+
+#[throws]
+fn main() {
+    let shutdown = Shutdown::install()?;
+    let conn = &connect_db()?;
+    let settings = Settings::load()?;
+    let worker = worker_id();
+    start_heartbeat(&worker)?;
+
+    while !shutdown.requested() {
+        release_stale_jobs(conn, TASKS)?;
+        run_due_tasks(conn, &settings.schedule)?;
+        if let Some(job) = claim_maintenance_job(conn, &worker)? {
+            println!("Running {}", job.tag);
+            match run_task(conn, &settings, &job.tag) {
+                Ok(()) => finish_job(conn, job.id)?,
+                Err(error) => {
+                    let error = Error::from(error);
+                    println!("Task {} failed: {:?}", job.tag, error);
+                    fail_job(conn, job.id, &format!("{:#}", error))?;
+                }
+            }
+        } else {
+            shutdown.sleep(Duration::from_secs(10));
+        }
+    }
+
+    println!("Shutting down.");
+}
+
+#[throws]
+fn claim_maintenance_job(conn: &PgConnection, worker: &str) -> Option<Job> {
+    for task in TASKS {
+        if let Some(job) = claim_job(conn, task, worker)? {
+            return Some(job);
+        }
+    }
+    None
+}
@@ -1,84 +1,200 @@
-#![feature(never_type)]
-
 use std::env::set_current_dir;
 use std::fs::copy;
-use std::fs::File;
+use std::fs::read_dir;
 use std::fs::remove_file;
 use std::fs::rename;
 use std::os::unix::fs::symlink;
 use std::path::Path;
 use std::process::Command;
-use std::thread;
 use std::time::Duration;
 
 use diesel::PgConnection;
 use fehler::throws;
-use libflate::gzip;
-use tar::Archive;
 
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Error;
 use pacman::connect_db;
-use pacman::db::delete_job;
+use pacman::consistency::create_empty_db;
+use pacman::consistency::read_pkgs_from_db;
+use pacman::db::create_package_event;
+use pacman::db::fail_job;
+use pacman::db::finish_job;
+use pacman::db::get_active_packages_by_repo;
 use pacman::db::get_package;
+use pacman::db::models::NewPackageEvent;
 use pacman::db::models::Package;
+use pacman::db::models::PackageEventKind;
+use pacman::db::models::WebhookEvent;
 use pacman::db::remove_package;
 use pacman::db::set_package_active;
+use pacman::db::set_package_inactive;
 use pacman::format_pkg_filename;
-use pacman::jobs::create_check_deps;
+use pacman::jobs::create_check_deps_with_layered;
 use pacman::jobs::get_repo_action;
+use pacman::jobs::get_repo_rebuild;
+use pacman::jobs::RepoAction;
 use pacman::jobs::RepoActionOp;
-use pacman::parse_pkg_name;
+use pacman::jobs::RepoRebuild;
+use pacman::notification::notify_failed_job;
+use pacman::worker::release_stale_jobs;
+use pacman::worker::start_heartbeat;
+use pacman::worker::worker_id;
+use pacman::webhook::package_data;
+use pacman::webhook::trigger_webhooks;
+use pacman::worker::Shutdown;
 
-fn main() -> Result<!, Error> {
+#[throws]
+fn main() {
+    let shutdown = Shutdown::install()?;
     let conn = &connect_db()?;
 
     set_current_dir("worker")
         .with_context(|| "Failed to switch to 'worker' directory")?;
+    clean_worker_dir()
+        .with_context(|| "Failed to clean up 'worker' directory")?;
+    let worker = worker_id();
+    start_heartbeat(&worker)?;
 
-    loop {
-        if let Some((id, repo_action)) = get_repo_action(conn, "worker")? {
-            let package = get_package(conn, repo_action.package_id)
-                .with_context(|| "Failed to retrieve package entry")?;
-            match repo_action.operation {
-                RepoActionOp::Add => {
-                    println!("Adding {:?}", package);
-                    perform_repo_add(conn, &package)
-                        .with_context(|| "Failed to perform repo add action")?;
-                }
-                RepoActionOp::Remove => {
-                    println!("Removing {:?}", package);
-                    perform_repo_rm(conn, &package)
-                        .with_context(|| "Failed to perform repo remove action")?;
-                }
-            };
-            delete_job(conn, id)
-                .with_context(|| "Failed to delete add action")?;
-            create_check_deps(conn, package.repo_id)
-                .with_context(|| "Failed to create check deps job")?;
+    while !shutdown.requested() {
+        release_stale_jobs(conn, &["repo_action", "repo_rebuild"])?;
+        if let Some((id, repo_action)) = get_repo_action(conn, &worker)? {
+            let result = perform_repo_action(conn, repo_action);
+            complete_job(conn, id, result)?;
+        } else if let Some((id, repo_rebuild)) = get_repo_rebuild(conn, &worker)? {
+            let result = perform_repo_rebuild(conn, repo_rebuild);
+            complete_job(conn, id, result)?;
         } else {
-            thread::sleep(Duration::from_secs(10));
+            shutdown.sleep(Duration::from_secs(10));
         }
     }
+
+    println!("Shutting down.");
 }
 
+/// Removes whatever a previous run may have left behind when it was killed in
+/// the middle of a repo action: the links and databases in the worker directory
+/// and half-copied databases in the repository directory.
 #[throws]
-fn perform_repo_add(conn: &PgConnection, package: &Package) {
-    let package_file = format_pkg_filename(&package);
-    remove_file(&package_file).ok();
+fn clean_worker_dir() {
+    for entry in read_dir(".")? {
+        let path = entry?.path();
+        println!("Removing stale file {}", path.display());
+        remove_file(path)?;
+    }
+    for entry in read_dir("../repos")? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |e| e == "new") {
+            println!("Removing stale file {}", path.display());
+            remove_file(path)?;
+        }
+    }
+}
 
-    let signature_file = format!("{}.sig", package_file);
-    remove_file(&signature_file).ok();
+#[throws]
+fn complete_job(conn: &PgConnection, id: i32, result: Result<(), Error>) {
+    match result {
+        Ok(()) => {
+            finish_job(conn, id)
+                .with_context(|| "Failed to finish job")?;
+        }
+        Err(error) => {
+            println!("Job {} failed: {:?}", id, error);
+            let error = format!("{:#}", error);
+            fail_job(conn, id, &error)
+                .with_context(|| "Failed to mark job as failed")?;
+            // The job failed either way, so the worker keeps going.
+            if let Err(error) = notify_failed_job(conn, id, &error) {
+                println!("Failed to notify about failed job {}: {:?}", id, error);
+            }
+        }
+    }
+}
 
-    let source = format!("../packages/{}", package.archive);
-    symlink(&source, &package_file)
-        .with_context(|| "Failed to link package archive")?;
+#[throws]
+fn perform_repo_action(conn: &PgConnection, repo_action: RepoAction) {
+    let package = get_package(conn, repo_action.package_id)
+        .with_context(|| "Failed to retrieve package entry")?;
+    let event = match repo_action.operation {
+        RepoActionOp::Add => {
+            println!("Adding {:?}", package);
+            perform_repo_add(conn, &package)
+                .with_context(|| "Failed to perform repo add action")?;
+            WebhookEvent::PackageActivated
+        }
+        RepoActionOp::Remove => {
+            println!("Removing {:?}", package);
+            perform_repo_rm(conn, &package)
+                .with_context(|| "Failed to perform repo remove action")?;
+            WebhookEvent::PackageRemoved
+        }
+    };
+    // The repository has changed already, so the action must not fail (and
+    // be repeated) because of the webhooks.
+    if let Err(error) = trigger_webhooks(conn, package.repo_id, event, serde_json::json!({ "package": package_data(&package) })) {
+        println!("Failed to trigger webhooks for {:?}: {:?}", package, error);
+    }
+    create_check_deps_with_layered(conn, package.repo_id)
+        .with_context(|| "Failed to create check deps job")?;
+}
 
-    let source = format!("../packages/{}", package.signature);
-    symlink(&source, &signature_file)
-        .with_context(|| "Failed to link package signature")?;
+#[throws]
+fn perform_repo_rebuild(conn: &PgConnection, repo_rebuild: RepoRebuild) {
+    let repo_id = repo_rebuild.repo_id;
+    println!("Rebuilding repo {}", repo_id);
+
+    // Start from scratch instead of linking the current database, which must
+    // not be overwritten before the new one is complete.
+    let source_db = format!("../repos/{}.db.tar.gz", repo_id);
+    remove_worker_db();
+    create_empty_db("database.db.tar.gz")
+        .with_context(|| "Failed to create empty DB")?;
 
+    let mut package_files = Vec::new();
+    let mut missing_archives = Vec::new();
+    for package in get_active_packages_by_repo(conn, repo_id)? {
+        if Path::new("../packages").join(&package.archive).exists() {
+            package_files.push(link_package(&package)?);
+        } else {
+            println!("Leaving out {:?}, because its archive is missing", package);
+            missing_archives.push(package);
+        }
+    }
+
+    if !package_files.is_empty() {
+        let output = Command::new("repo-add")
+            .arg("database.db.tar.gz")
+            .args(&package_files)
+            .output()
+            .with_context(|| "Failed to run repo-add command")?;
+        if !output.status.success() {
+            Err(anyhow!("Invocation of repo-add failed with exit code {:?}: {}",
+                output.status.code(), String::from_utf8_lossy(&output.stderr).trim()))?
+        }
+    }
+
+    update_source_db(&source_db)?;
+
+    // Packages that could not be added must not stay active, or the drift
+    // that the rebuild was meant to fix remains.
+    for package in &missing_archives {
+        set_package_inactive(conn, package.id)
+            .with_context(|| "Failed to deactivate package entry")?;
+    }
+    create_check_deps_with_layered(conn, repo_id)
+        .with_context(|| "Failed to create check deps job")?;
+
+    if !missing_archives.is_empty() {
+        let packages = missing_archives.iter()
+            .map(format_pkg_filename)
+            .collect::<Vec<_>>();
+        Err(anyhow!("Deactivated packages whose archive is missing: {}", packages.join(", ")))?
+    }
+}
+
+#[throws]
+fn perform_repo_add(conn: &PgConnection, package: &Package) {
+    let package_file = link_package(package)?;
     let source_db = link_source_db(package.repo_id)?;
 
     let output = Command::new("repo-add")
@@ -87,8 +203,8 @@ fn perform_repo_add(conn: &PgConnection, package: &Package) {
         .output()
         .with_context(|| "Failed to run repo-add command")?;
     if !output.status.success() {
-        Err(anyhow!("Invocation of repo-add failed with exit code {:?}",
-            output.status.code()))?
+        Err(anyhow!("Invocation of repo-add failed with exit code {:?}: {}",
+            output.status.code(), String::from_utf8_lossy(&output.stderr).trim()))?
     }
 
     update_source_db(&source_db)?;
@@ -109,8 +225,8 @@ fn perform_repo_rm(conn: &PgConnection, package: &Package) {
             .output()
             .with_context(|| "Failed to run repo-remove command")?;
         if !output.status.success() {
-            Err(anyhow!("Invocation of repo-remove failed with exit code {:?}",
-                output.status.code()))?
+            Err(anyhow!("Invocation of repo-remove failed with exit code {:?}: {}",
+                output.status.code(), String::from_utf8_lossy(&output.stderr).trim()))?
         }
     }
 
@@ -121,39 +237,36 @@ fn perform_repo_rm(conn: &PgConnection, package: &Package) {
     remove_file(format!("../packages/{}", package.signature))
         .with_context(|| "Failed to remove package signature")?;
 
+    create_package_event(conn, &NewPackageEvent::new(package, PackageEventKind::Removed))
+        .with_context(|| "Failed to record package removal")?;
     remove_package(conn, package.id)
         .with_context(|| "Failed to remove package entry")?;
 }
 
+/// Links the archive and signature of the package into the worker directory
+/// and returns the name of the linked archive.
 #[throws]
-fn read_pkgs_from_db(db: impl AsRef<Path>) -> Vec<(String, String)> {
-    let compressed_file = File::open(db)?;
-    let uncompressed_file = gzip::Decoder::new(compressed_file)?;
-    let mut archive = Archive::new(uncompressed_file);
-    archive.entries()?
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|entry| entry.path())
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .filter_map(|path| {
-            let components = path.components().collect::<Vec<_>>();
-            if components.len() == 1 {
-                components[0].as_os_str().to_str()
-            } else {
-                None
-            }
-        })
-        .map(parse_pkg_name)
-        .collect::<Result<Vec<_>, _>>()?
+fn link_package(package: &Package) -> String {
+    let package_file = format_pkg_filename(&package);
+    remove_file(&package_file).ok();
+
+    let signature_file = format!("{}.sig", package_file);
+    remove_file(&signature_file).ok();
+
+    let source = format!("../packages/{}", package.archive);
+    symlink(&source, &package_file)
+        .with_context(|| "Failed to link package archive")?;
+
+    let source = format!("../packages/{}", package.signature);
+    symlink(&source, &signature_file)
+        .with_context(|| "Failed to link package signature")?;
+
+    package_file
 }
 
 #[throws]
 fn link_source_db(repo_id: i32) -> String {
-    remove_file("database.db").ok();
-    remove_file("database.db.tar.gz").ok();
-    remove_file("database.files").ok();
-    remove_file("database.files.tar.gz").ok();
+    remove_worker_db();
 
     let repo_source = format!("../repos/{}.db.tar.gz", repo_id);
     if Path::new(&repo_source).exists() {
@@ -163,6 +276,13 @@ fn link_source_db(repo_id: i32) -> String {
     repo_source
 }
 
+fn remove_worker_db() {
+    remove_file("database.db").ok();
+    remove_file("database.db.tar.gz").ok();
+    remove_file("database.files").ok();
+    remove_file("database.files.tar.gz").ok();
+}
+
 #[throws]
 fn update_source_db(source_db: &String) {
     let repo_source_tmp = format!("{}.new", source_db);
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use anyhow::Error;
+use chrono::Utc;
+use diesel::PgConnection;
+use fehler::throws;
+
+use pacman::connect_db;
+use pacman::db::fail_job;
+use pacman::db::finish_job;
+use pacman::db::get_webhook_delivery;
+use pacman::db::models::Webhook;
+use pacman::db::models::WebhookDelivery;
+use pacman::db::reschedule_job;
+use pacman::db::set_webhook_delivery_attempt;
+use pacman::jobs::DeliverWebhook;
+use pacman::jobs::get_deliver_webhook;
+use pacman::notification::notify_failed_job;
+use pacman::webhook::deliver;
+use pacman::webhook::retry_delay;
+use pacman::worker::release_stale_jobs;
+use pacman::worker::start_heartbeat;
+use pacman::worker::worker_id;
+use pacman::worker::Shutdown;
+
+#[throws]
+fn main() {
+    let shutdown = Shutdown::install()?;
+    let conn = &connect_db()?;
+    let worker = worker_id();
+    start_heartbeat(&worker)?;
+
+    while !shutdown.requested() {
+        release_stale_jobs(conn, &["deliver_webhook"])?;
+        if let Some((id, deliver_webhook)) = get_deliver_webhook(conn, &worker)? {
+            if let Err(error) = perform_delivery(conn, id, deliver_webhook) {
+                println!("Webhook delivery {} failed: {:?}", id, error);
+                fail_job(conn, id, &format!("{:#}", error))?;
+            }
+        } else {
+            shutdown.sleep(Duration::from_secs(10));
+        }
+    }
+
+    println!("Shutting down.");
+}
+
+#[throws]
+fn perform_delivery(conn: &PgConnection, job_id: i32, deliver_webhook: DeliverWebhook) {
+    match get_webhook_delivery(conn, deliver_webhook.delivery_id)? {
+        Some((delivery, webhook)) => attempt_delivery(conn, job_id, &delivery, &webhook)?,
+        None => {
+            println!("Skipping delivery {}, because its webhook was removed.", deliver_webhook.delivery_id);
+            finish_job(conn, job_id)?;
+        }
+    }
+}
+
+/// Attempts the delivery and records the outcome. A failed attempt is retried
+/// later, until the attempts are used up and the job fails.
+#[throws]
+fn attempt_delivery(conn: &PgConnection, job_id: i32, delivery: &WebhookDelivery, webhook: &Webhook) {
+    let (status_code, error) = match deliver(webhook, delivery) {
+        Ok(status) if (200..300).contains(&status) => (Some(status as i32), None),
+        Ok(status) => (Some(status as i32), Some(format!("{} responded with status {}", webhook.url, status))),
+        Err(error) => (None, Some(format!("{:#}", Error::from(error))))
+    };
+    let now = Utc::now().naive_utc();
+    set_webhook_delivery_attempt(conn, delivery.id, status_code, error.as_deref(), now)?;
+
+    match error {
+        None => {
+            println!("Delivered {} to {}.", delivery.event, webhook.url);
+            finish_job(conn, job_id)?;
+        }
+        Some(error) => match retry_delay(delivery.attempts + 1) {
+            Some(delay) => {
+                println!("Delivery {} failed, retrying in {} minutes: {}", delivery.id, delay.num_minutes(), error);
+                reschedule_job(conn, job_id, now + delay)?;
+            }
+            None => {
+                println!("Delivery {} failed for the last time: {}", delivery.id, error);
+                fail_job(conn, job_id, &error)?;
+                if let Err(error) = notify_failed_job(conn, job_id, &error) {
+                    println!("Failed to notify about failed job {}: {:?}", job_id, error);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Error;
+use fehler::throws;
+use prettytable::Cell;
+use prettytable::format::consts::FORMAT_CLEAN;
+use prettytable::Row;
+use prettytable::Table;
+use structopt::StructOpt;
+
+use pacman::client::Client;
+use pacman::client::ClientConfig;
+use pacman::client::find_uploads;
+use pacman::client::Removal;
+use pacman::client::RepoRef;
+
+/// Manages the packages of a paketbote repository.
+///
+/// The server, the access token and the repository are read from
+/// ~/.config/paketbote/pb.toml, e.g.:
+///
+///     server = "https://paketbote.tk"
+///     token = "<access token>"
+///     repo = "username/repository"
+#[derive(StructOpt)]
+#[structopt(name = "pb")]
+struct Options {
+    /// Reads the configuration from this file instead.
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// The repository to work on, as account/repo.
+    #[structopt(long, short)]
+    repo: Option<RepoRef>,
+
+    #[structopt(subcommand)]
+    command: Command
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Uploads packages, each with the signature next to it.
+    ///
+    /// Patterns like '*.pkg.tar.zst' are expanded, and signatures among the
+    /// matched files are skipped.
+    Upload {
+        /// Waits up to this many seconds until the packages are added.
+        #[structopt(long)]
+        wait: Option<u64>,
+        #[structopt(required = true)]
+        files: Vec<String>
+    },
+    /// Lists the packages in the repository.
+    List,
+    /// Shows a package, given by its file name.
+    Info {
+        package: String
+    },
+    /// Removes packages, given by their file names.
+    Remove {
+        /// Removes the packages even if that breaks other packages.
+        #[structopt(long)]
+        force: bool,
+        #[structopt(required = true)]
+        packages: Vec<String>
+    },
+    /// Adds a package back to the repository database.
+    Activate {
+        package: String
+    },
+    /// Removes the packages that are inactive and have newer versions.
+    DeleteObsolete {
+        /// Removes the packages even if that breaks other packages.
+        #[structopt(long)]
+        force: bool
+    },
+    /// Manages access tokens.
+    Token(TokenCommand)
+}
+
+#[derive(StructOpt)]
+enum TokenCommand {
+    /// Lists the access tokens of the account.
+    List,
+    /// Creates an access token and prints it.
+    Create {
+        name: String
+    },
+    /// Deletes an access token, given by its id.
+    Delete {
+        id: i32
+    }
+}
+
+#[throws]
+fn main() {
+    let options = Options::from_args();
+    let config_path = options.config.clone()
+        .or_else(ClientConfig::default_path)
+        .ok_or_else(|| anyhow!("Cannot find the configuration directory, use --config"))?;
+    let config = ClientConfig::load(&config_path)?;
+    let client = Client::new(&config.server, config.token)?;
+    let repo = options.repo.or(config.repo);
+    let repo = || repo.as_ref()
+        .ok_or_else(|| anyhow!("No repository given, use --repo or set repo in {}", config_path.display()));
+
+    match options.command {
+        Command::Upload { wait, files } => upload(&client, repo()?, wait, &files)?,
+        Command::List => list(&client, repo()?)?,
+        Command::Info { package } => info(&client, repo()?, &package)?,
+        Command::Remove { force, packages } => {
+            for package in packages {
+                print_removal(client.remove(repo()?, &package, force)?);
+            }
+        }
+        Command::Activate { package } => {
+            let job = client.activate(repo()?, &package)?;
+            println!("Activating {} in job {}.", package, job.id);
+        }
+        Command::DeleteObsolete { force } =>
+            print_removal(client.delete_obsolete(repo()?, force)?),
+        Command::Token(command) => token(&client, command)?
+    }
+}
+
+#[throws]
+fn upload(client: &Client, repo: &RepoRef, wait: Option<u64>, files: &[String]) {
+    for (package, signature) in find_uploads(files)? {
+        println!("Uploading {} to {}...", package.display(), repo);
+        let response = client.upload(repo, &package, &signature, wait)?;
+        match response.job.error {
+            Some(error) => println!("Job {} failed: {}", response.job.id, error),
+            None => println!("Uploaded {} {} ({}), job {} is {}.",
+                response.package.name, response.package.version, response.package.arch,
+                response.job.id, response.job.status)
+        }
+    }
+}
+
+#[throws]
+fn list(client: &Client, repo: &RepoRef) {
+    let mut table = Table::new();
+    table.set_format(*FORMAT_CLEAN);
+    for package in client.packages(repo)? {
+        let state = if package.active { "" } else { "inactive" };
+        table.add_row(Row::new(vec![
+            Cell::new(&package.name),
+            Cell::new(&package.version),
+            Cell::new(&package.arch),
+            Cell::new(state)]));
+    }
+    table.printstd();
+}
+
+#[throws]
+fn info(client: &Client, repo: &RepoRef, file: &str) {
+    let details = client.package(repo, file)?;
+    let package = &details.package;
+    println!("Name:         {}", package.name);
+    println!("Version:      {}", package.version);
+    println!("Architecture: {}", package.arch);
+    println!("Size:         {}", package.size);
+    println!("Uploaded:     {}", package.created);
+    println!("Active:       {}", package.active);
+    println!("Archive:      {}", package.archive);
+    let dependencies = |kind: &str, dependencies: &[pacman::client::Dependency]| {
+        for dependency in dependencies {
+            let missing = if dependency.missing { " (missing)" } else { "" };
+            println!("{:<13} {}{}", kind, dependency.depends, missing);
+        }
+    };
+    dependencies("Depends:", &details.depends);
+    dependencies("Optdepends:", &details.optdepends);
+    dependencies("Makedepends:", &details.makedepends);
+    dependencies("Checkdepends:", &details.checkdepends);
+    for provides in &details.provides {
+        println!("Provides:     {}", provides);
+    }
+    for required_by in &details.required_by {
+        println!("Required by:  {}/{} ({})", required_by.repo, required_by.package, required_by.dependency);
+    }
+}
+
+fn print_removal(removal: Removal) {
+    match removal {
+        Removal::Removed(jobs) if jobs.is_empty() => println!("Nothing to remove."),
+        Removal::Removed(jobs) => for job in jobs {
+            println!("Removal scheduled in job {}: {}", job.id, job.description);
+        },
+        Removal::Refused(preview) => {
+            println!("Refusing to remove {}, because it would break:", preview.packages.join(", "));
+            for breakage in preview.breakages {
+                println!("  {}/{} ({}) needs {}", breakage.repo, breakage.package, breakage.arch, breakage.dependency);
+            }
+            println!("Use --force to remove anyway.");
+        }
+    }
+}
+
+#[throws]
+fn token(client: &Client, command: TokenCommand) {
+    match command {
+        TokenCommand::List => {
+            for token in client.tokens()? {
+                println!("{}\t{}", token.id, token.name);
+            }
+        }
+        TokenCommand::Create { name } => {
+            let token = client.create_token(&name)?;
+            println!("Created token {}: {}", token.name, token.the_token);
+        }
+        TokenCommand::Delete { id } => {
+            client.delete_token(id)?;
+            println!("Deleted token {}.", id);
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! A client for the JSON API under `/api/v1`, as used by `pb`.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use fehler::throws;
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::RequestBuilder;
+use reqwest::blocking::Response;
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::error::Error;
+
+/// Where `pb` finds the server, the access token and the repository it
+/// works on, unless they are given on the command line.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    pub server: String,
+    pub token: Option<String>,
+    /// The repository as `account/repo`.
+    pub repo: Option<RepoRef>
+}
+
+impl Default for ClientConfig {
+    fn default() -> ClientConfig {
+        ClientConfig { server: "https://paketbote.tk".to_owned(), token: None, repo: None }
+    }
+}
+
+impl ClientConfig {
+    /// `~/.config/paketbote/pb.toml` on Linux.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("paketbote").join("pb.toml"))
+    }
+
+    /// Reads the configuration from the file, which does not have to exist.
+    #[throws]
+    pub fn load(path: &Path) -> ClientConfig {
+        if path.exists() {
+            let config = fs::read_to_string(path)?;
+            toml::from_str(&config)
+                .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))?
+        } else {
+            ClientConfig::default()
+        }
+    }
+}
+
+/// A repository, given as `account/repo`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct RepoRef {
+    pub account: String,
+    pub repo: String
+}
+
+impl FromStr for RepoRef {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        let parts = string.splitn(2, '/').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [account, repo] if !account.is_empty() && !repo.is_empty() && !repo.contains('/') =>
+                RepoRef { account: (*account).to_owned(), repo: (*repo).to_owned() },
+            _ => Err(format!("Invalid repository '{}', expected 'account/repo'", string))?
+        }
+    }
+}
+
+impl std::convert::TryFrom<String> for RepoRef {
+    type Error = Error;
+    fn try_from(string: String) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
+impl fmt::Display for RepoRef {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}/{}", self.account, self.repo)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total_items: usize,
+    pub current_page: usize,
+    pub total_pages: usize
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub size: i32,
+    pub archive: String,
+    pub created: String,
+    pub active: bool,
+    pub missing_deps: Vec<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PackageDetails {
+    #[serde(flatten)]
+    pub package: Package,
+    pub depends: Vec<Dependency>,
+    pub optdepends: Vec<Dependency>,
+    pub makedepends: Vec<Dependency>,
+    pub checkdepends: Vec<Dependency>,
+    pub provides: Vec<String>,
+    pub required_by: Vec<RequiredBy>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Dependency {
+    pub depends: String,
+    pub missing: bool
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequiredBy {
+    pub repo: String,
+    pub package: String,
+    pub dependency: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Job {
+    pub id: i32,
+    pub description: String,
+    pub status: String,
+    pub error: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadResponse {
+    pub package: UploadedPackage,
+    pub job: Job
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadedPackage {
+    pub name: String,
+    pub version: String,
+    pub arch: String
+}
+
+/// The outcome of a removal, see `DELETE /repos/<account>/<repo>/packages/<file>`.
+#[derive(Debug)]
+pub enum Removal {
+    Removed(Vec<Job>),
+    Refused(RemovalPreview)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemovalPreview {
+    pub packages: Vec<String>,
+    pub breakages: Vec<BrokenPackage>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BrokenPackage {
+    pub repo: String,
+    pub package: String,
+    pub arch: String,
+    pub dependency: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Token {
+    pub id: i32,
+    pub name: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewToken {
+    pub name: String,
+    pub the_token: String
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String
+}
+
+pub struct Client {
+    server: Url,
+    token: Option<String>,
+    http: reqwest::blocking::Client
+}
+
+impl Client {
+    #[throws]
+    pub fn new(server: &str, token: Option<String>) -> Client {
+        let server = Url::parse(server)
+            .map_err(|e| format!("Invalid server URL {}: {}", server, e))?;
+        if server.cannot_be_a_base() {
+            Err(format!("Invalid server URL {}", server))?
+        }
+        let http = reqwest::blocking::Client::builder()
+            .user_agent("pb")
+            .timeout(Duration::from_secs(600))
+            .build()?;
+        Client { server, token, http }
+    }
+
+    #[throws]
+    pub fn packages(&self, repo: &RepoRef) -> Vec<Package> {
+        let mut packages = Vec::new();
+        let mut page = 0;
+        loop {
+            let url = self.url(&["repos", &repo.account, &repo.repo, "packages"], &[("page", &page.to_string())]);
+            let response: Paginated<Package> = self.send(self.request(Method::GET, url))?;
+            packages.extend(response.items);
+            page += 1;
+            if page >= response.total_pages {
+                break;
+            }
+        }
+        packages
+    }
+
+    #[throws]
+    pub fn package(&self, repo: &RepoRef, file: &str) -> PackageDetails {
+        let url = self.url(&["repos", &repo.account, &repo.repo, "packages", file], &[]);
+        self.send(self.request(Method::GET, url))?
+    }
+
+    /// Uploads the package and its signature. With `wait`, the response is
+    /// only sent once the package was added to the repository (or the wait
+    /// timed out).
+    #[throws]
+    pub fn upload(&self, repo: &RepoRef, package: &Path, signature: &Path, wait: Option<u64>) -> UploadResponse {
+        let file = package.file_name()
+            .and_then(|f| f.to_str())
+            .ok_or_else(|| format!("Invalid file name {}", package.display()))?;
+        let wait = wait.map(|w| w.to_string());
+        let query = wait.iter().map(|w| ("wait", w.as_str())).collect::<Vec<_>>();
+        let url = self.url(&["repos", &repo.account, &repo.repo, "packages", file], &query);
+        let form = Form::new()
+            .file("package", package)?
+            .file("signature", signature)?;
+        self.send(self.request(Method::POST, url).multipart(form))?
+    }
+
+    #[throws]
+    pub fn activate(&self, repo: &RepoRef, file: &str) -> Job {
+        let url = self.url(&["repos", &repo.account, &repo.repo, "packages", file, "activate"], &[]);
+        self.send(self.request(Method::POST, url))?
+    }
+
+    #[throws]
+    pub fn remove(&self, repo: &RepoRef, file: &str, force: bool) -> Removal {
+        let url = self.url(&["repos", &repo.account, &repo.repo, "packages", file], &force_query(force));
+        self.send_removal(self.request(Method::DELETE, url))?
+    }
+
+    #[throws]
+    pub fn delete_obsolete(&self, repo: &RepoRef, force: bool) -> Removal {
+        let url = self.url(&["repos", &repo.account, &repo.repo, "delete-obsolete"], &force_query(force));
+        self.send_removal(self.request(Method::POST, url))?
+    }
+
+    #[throws]
+    pub fn tokens(&self) -> Vec<Token> {
+        self.send(self.request(Method::GET, self.url(&["tokens"], &[])))?
+    }
+
+    #[throws]
+    pub fn create_token(&self, name: &str) -> NewToken {
+        let request = self.request(Method::POST, self.url(&["tokens"], &[]))
+            .json(&json!({ "name": name }));
+        self.send(request)?
+    }
+
+    #[throws]
+    pub fn delete_token(&self, id: i32) {
+        let response = self.request(Method::DELETE, self.url(&["tokens", &id.to_string()], &[])).send()?;
+        check(response)?;
+    }
+
+    fn url(&self, segments: &[&str], query: &[(&str, &str)]) -> Url {
+        let mut url = self.server.clone();
+        url.path_segments_mut()
+            .expect("the server URL can be a base")
+            .pop_if_empty()
+            .extend(&["api", "v1"])
+            .extend(segments);
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request
+        }
+    }
+
+    #[throws]
+    fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> T {
+        check(request.send()?)?.json()?
+    }
+
+    #[throws]
+    fn send_removal(&self, request: RequestBuilder) -> Removal {
+        let response = request.send()?;
+        if response.status() == StatusCode::CONFLICT {
+            Removal::Refused(response.json()?)
+        } else {
+            Removal::Removed(check(response)?.json()?)
+        }
+    }
+}
+
+fn force_query(force: bool) -> Vec<(&'static str, &'static str)> {
+    if force { vec![("force", "true")] } else { vec![] }
+}
+
+/// Turns error responses into errors with the message from the server.
+#[throws]
+fn check(response: Response) -> Response {
+    let status = response.status();
+    if status.is_success() {
+        response
+    } else {
+        let message = response.json::<ApiError>()
+            .map(|e| e.message)
+            .unwrap_or_else(|_| status.canonical_reason().unwrap_or("Unknown error").to_owned());
+        Err(format!("{} ({})", message, status.as_u16()))?
+    }
+}
+
+/// Whether the file is a package archive, like `foo-1.0-1-x86_64.pkg.tar.zst`,
+/// rather than a signature or something else.
+pub fn is_package_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .map_or(false, |f| f.contains(".pkg.tar") && !f.ends_with(".sig"))
+}
+
+/// Expands the patterns to the packages they match, each with the signature
+/// next to it. Signatures and other files matched by the patterns are skipped.
+#[throws]
+pub fn find_uploads(patterns: &[String]) -> Vec<(PathBuf, PathBuf)> {
+    let mut uploads = Vec::new();
+    for pattern in patterns {
+        let paths = glob::glob(pattern)
+            .map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Cannot read {}: {}", e.path().display(), e.error()))?;
+        let packages = paths.into_iter()
+            .filter(|p| is_package_file(p))
+            .collect::<Vec<_>>();
+        if packages.is_empty() {
+            Err(format!("No packages match {}", pattern))?
+        }
+        for package in packages {
+            let signature = signature_path(&package);
+            if !signature.exists() {
+                Err(format!("The signature {} is missing", signature.display()))?
+            }
+            uploads.push((package, signature));
+        }
+    }
+    uploads
+}
+
+fn signature_path(package: &Path) -> PathBuf {
+    let mut signature = package.as_os_str().to_owned();
+    signature.push(".sig");
+    PathBuf::from(signature)
+}
+
+#[test]
+fn test_repo_ref() {
+    let repo: RepoRef = "alice/stuff".parse().unwrap();
+    assert_eq!(repo, RepoRef { account: "alice".to_owned(), repo: "stuff".to_owned() });
+    assert_eq!(repo.to_string(), "alice/stuff");
+    assert!("alice".parse::<RepoRef>().is_err());
+    assert!("alice/".parse::<RepoRef>().is_err());
+    assert!("alice/stuff/more".parse::<RepoRef>().is_err());
+}
+
+#[test]
+fn test_is_package_file() {
+    assert!(is_package_file(Path::new("out/foo-1.0-1-x86_64.pkg.tar.zst")));
+    assert!(is_package_file(Path::new("foo-1:2.0-1-any.pkg.tar.xz")));
+    assert!(!is_package_file(Path::new("foo-1.0-1-x86_64.pkg.tar.zst.sig")));
+    assert!(!is_package_file(Path::new("PKGBUILD")));
+}
+
+#[test]
+fn test_signature_path() {
+    assert_eq!(signature_path(Path::new("out/foo-1.0-1-x86_64.pkg.tar.zst")),
+        Path::new("out/foo-1.0-1-x86_64.pkg.tar.zst.sig"));
+}
+
+#[test]
+fn test_client_config() {
+    let config: ClientConfig = toml::from_str("token = \"secret\"\nrepo = \"alice/stuff\"").unwrap();
+    assert_eq!(config.server, "https://paketbote.tk");
+    assert_eq!(config.token.as_deref(), Some("secret"));
+    assert_eq!(config.repo.unwrap().to_string(), "alice/stuff");
+}
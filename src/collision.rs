@@ -0,0 +1,45 @@
+use diesel::PgConnection;
+use fehler::throws;
+
+use crate::arch::arch_matches;
+use crate::arch::repo_archs;
+use crate::db::get_active_packages_by_repo;
+use crate::error::Error;
+use crate::syncdb::UpstreamDbs;
+
+/// An active package of the repository that has the same name as a package of
+/// an upstream repository. Which of the two pacman installs depends on the
+/// order of the repositories in pacman.conf.
+#[derive(Debug)]
+pub struct UpstreamCollision {
+    pub package_id: i32,
+    pub arch: String,
+    pub upstream_repo: String,
+    pub upstream_version: String,
+}
+
+/// Looks up the active packages of the repository in the upstream repositories
+/// of every architecture the repository serves.
+#[throws]
+pub fn upstream_collisions(conn: &PgConnection, repo_id: i32, upstream: &UpstreamDbs) -> Vec<UpstreamCollision> {
+    let packages = get_active_packages_by_repo(conn, repo_id)?;
+    let archs = repo_archs(conn, repo_id)?;
+
+    let mut collisions = Vec::new();
+    for arch in &archs {
+        let upstream = upstream.get(arch)?;
+        for package in packages.iter().filter(|p| arch_matches(&p.arch, arch)) {
+            for db in &upstream.0 {
+                if let Some(pkg) = db.pkg(&package.name) {
+                    collisions.push(UpstreamCollision {
+                        package_id: package.id,
+                        arch: arch.clone(),
+                        upstream_repo: db.name.clone(),
+                        upstream_version: pkg.version.clone()
+                    });
+                }
+            }
+        }
+    }
+    collisions
+}
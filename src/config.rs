@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+
+use fehler::throws;
+use reqwest::Url;
+use rocket::config::Config;
+use rocket::config::Table;
+use rocket::config::Value;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::get_config;
+
+/// Application specific settings. They are read from the extras of the Rocket
+/// configuration, so they live next to the database configuration in
+/// `Rocket.toml` (or in `ROCKET_*` environment variables).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Names of the accounts that may see the administration pages.
+    pub admins: Vec<String>,
+    /// Where the web interface is reachable, for links that leave it, like
+    /// those in feeds.
+    #[serde(default = "default_url")]
+    pub url: String,
+    pub schedule: Schedule,
+    pub retention: Retention,
+    pub upstream: Upstream,
+    pub aur: Aur,
+    /// The server that notifications are sent through. Without one,
+    /// notifications are queued but not sent.
+    pub smtp: Option<Smtp>,
+    /// The GnuPG home directory with the keys that upstream databases are
+    /// verified with, e.g. as set up by `pacman-key --gpgdir <dir> --init`.
+    #[serde(default = "default_keyring")]
+    pub keyring: String,
+}
+
+fn default_url() -> String {
+    "https://paketbote.tk".to_owned()
+}
+
+fn default_keyring() -> String {
+    "pacman-keyring".to_owned()
+}
+
+impl Settings {
+    #[throws]
+    pub fn from_config(config: &Config) -> Settings {
+        let extras: Table = config.extras()
+            .map(|(key, value)| (key.to_owned(), value.clone()))
+            .collect();
+        Value::Table(extras).try_into::<Settings>()
+            .map_err(|e| format!("Invalid configuration: {}", e))?
+    }
+
+    #[throws]
+    pub fn load() -> Settings {
+        Settings::from_config(&get_config())?
+    }
+
+    pub fn is_admin(&self, account: &str) -> bool {
+        self.admins.iter().any(|admin| admin == account)
+    }
+}
+
+/// Intervals (in seconds) at which the periodic tasks are run. An interval of
+/// zero disables the task.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub check_aur_versions: u64,
+    pub check_upstream_versions: u64,
+    pub sync_pacman_dbs: u64,
+    pub send_notifications: u64,
+    pub retention: u64,
+    pub gc: u64,
+    pub scrub: u64,
+    pub consistency: u64,
+}
+
+impl Schedule {
+    pub fn tasks(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("check_aur_versions", self.check_aur_versions),
+            ("check_upstream_versions", self.check_upstream_versions),
+            ("sync_pacman_dbs", self.sync_pacman_dbs),
+            ("send_notifications", self.send_notifications),
+            ("retention", self.retention),
+            ("gc", self.gc),
+            ("scrub", self.scrub),
+            ("consistency", self.consistency),
+        ]
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            check_aur_versions: 24 * 60 * 60,
+            check_upstream_versions: 24 * 60 * 60,
+            sync_pacman_dbs: 30 * 60,
+            send_notifications: 60 * 60,
+            retention: 24 * 60 * 60,
+            gc: 24 * 60 * 60,
+            scrub: 7 * 24 * 60 * 60,
+            consistency: 24 * 60 * 60,
+        }
+    }
+}
+
+/// How long (in days) things are kept around before the retention task
+/// deletes them.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    pub finished_jobs: u64,
+    pub sent_notifications: u64,
+    pub webhook_deliveries: u64,
+    /// Obsolete packages are only deleted automatically if this is set.
+    pub obsolete_packages: Option<u64>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention { finished_jobs: 7, sent_notifications: 30, webhook_deliveries: 30, obsolete_packages: None }
+    }
+}
+
+/// How the AUR is queried for the versions of our packages.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Aur {
+    /// The endpoint of the AUR RPC interface.
+    pub rpc_url: String,
+    /// The maximum number of packages that are looked up per request.
+    pub batch_size: usize,
+}
+
+const AUR_RPC_URL: &str = "https://aur.archlinux.org/rpc.php";
+
+impl Default for Aur {
+    fn default() -> Self {
+        Aur { rpc_url: AUR_RPC_URL.to_owned(), batch_size: 100 }
+    }
+}
+
+impl Aur {
+    /// The page of the package in the AUR web interface, which is served by
+    /// the same host as the RPC interface.
+    pub fn package_url(&self, package: &str) -> String {
+        let mut url = Url::parse(&self.rpc_url)
+            .unwrap_or_else(|_| Url::parse(AUR_RPC_URL).unwrap());
+        url.set_path(&format!("/packages/{}", package));
+        url.set_query(None);
+        url.to_string()
+    }
+}
+
+/// The SMTP server that notification digests are sent through. For testing,
+/// a local sink like MailHog can be used with `tls = false`.
+#[derive(Debug, Deserialize)]
+pub struct Smtp {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// The sender address of the digests.
+    pub from: String,
+    /// Whether the connection has to be upgraded with STARTTLS.
+    #[serde(default = "default_smtp_tls")]
+    pub tls: bool,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_tls() -> bool {
+    true
+}
+
+/// The repositories, per architecture, that packages may depend on. They are
+/// synced periodically and used to resolve dependencies. Server URLs may
+/// contain `$repo` and `$arch`, like in `pacman.conf`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Upstream(pub BTreeMap<String, Vec<UpstreamRepo>>);
+
+impl Upstream {
+    pub fn archs(&self) -> Vec<&str> {
+        self.0.keys().map(|arch| arch.as_str()).collect()
+    }
+
+    /// The repositories for the architecture, or none if the architecture is
+    /// not configured.
+    pub fn repos(&self, arch: &str) -> &[UpstreamRepo] {
+        self.0.get(arch).map_or(&[], |repos| repos.as_slice())
+    }
+}
+
+impl Default for Upstream {
+    fn default() -> Self {
+        let arch_linux = "https://geo.mirror.pkgbuild.com/$repo/os/$arch";
+        let arch_linux_arm = "https://mirror.archlinuxarm.org/$arch/$repo";
+
+        let mut upstream = BTreeMap::new();
+        upstream.insert("x86_64".to_owned(), UpstreamRepo::many(
+            &["core", "extra", "multilib"], arch_linux, Verify::Required));
+        for arch in &["aarch64", "arm", "armv6h", "armv7h"] {
+            upstream.insert(arch.to_string(), UpstreamRepo::many(
+                &["core", "extra", "alarm", "aur"], arch_linux_arm, Verify::Required));
+        }
+        Upstream(upstream)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamRepo {
+    pub name: String,
+    pub servers: Vec<String>,
+    #[serde(default)]
+    pub verify: Verify,
+}
+
+/// Whether the signature of an upstream database is checked when it is synced.
+/// Signatures are required unless configured otherwise. With `optional`,
+/// databases that come without a signature are accepted, but a signature that
+/// is present has to be valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verify {
+    Required,
+    Optional,
+    Never,
+}
+
+impl Default for Verify {
+    fn default() -> Self {
+        Verify::Required
+    }
+}
+
+impl UpstreamRepo {
+    fn many(names: &[&str], server: &str, verify: Verify) -> Vec<UpstreamRepo> {
+        names.iter()
+            .map(|name| UpstreamRepo {
+                name: name.to_string(),
+                servers: vec![server.to_owned()],
+                verify
+            })
+            .collect()
+    }
+
+    pub fn server_urls(&self, arch: &str) -> Vec<String> {
+        self.servers.iter()
+            .map(|server| server.replace("$repo", &self.name).replace("$arch", arch))
+            .collect()
+    }
+}
+
+#[test]
+fn test_schedule() {
+    let schedule = "gc = 0\nscrub = 3600".parse::<Value>().unwrap()
+        .try_into::<Schedule>().unwrap();
+    let tasks = schedule.tasks();
+    assert!(tasks.contains(&("gc", 0)));
+    assert!(tasks.contains(&("scrub", 3600)));
+    assert!(tasks.contains(&("sync_pacman_dbs", 30 * 60)));
+    assert_eq!(tasks.len(), Schedule::default().tasks().len());
+    assert!("gc = \"daily\"".parse::<Value>().unwrap().try_into::<Schedule>().is_err());
+}
+
+#[test]
+fn test_server_urls() {
+    let repo = UpstreamRepo {
+        name: "core".to_owned(),
+        servers: vec![
+            "https://mirror.example.com/$repo/os/$arch".to_owned(),
+            "https://other.example.com/archlinux/$arch/$repo".to_owned(),
+        ],
+        verify: Verify::default()
+    };
+    assert_eq!(repo.server_urls("x86_64"), vec![
+        "https://mirror.example.com/core/os/x86_64",
+        "https://other.example.com/archlinux/x86_64/core",
+    ]);
+    assert_eq!(Verify::default(), Verify::Required);
+}
+
+#[test]
+fn test_default_upstream() {
+    let upstream = Upstream::default();
+    assert!(!upstream.archs().is_empty());
+    for arch in upstream.archs() {
+        for repo in upstream.repos(arch) {
+            assert_eq!(repo.verify, Verify::Required, "{} ({})", repo.name, arch);
+            assert!(repo.servers.iter().all(|s| s.starts_with("https://")), "{} ({})", repo.name, arch);
+        }
+    }
+}
+
+#[test]
+fn test_aur_package_url() {
+    assert_eq!(Aur::default().package_url("foo"), "https://aur.archlinux.org/packages/foo");
+    let aur = Aur { rpc_url: "http://localhost:8080/rpc/?v=5".to_owned(), batch_size: 100 };
+    assert_eq!(aur.package_url("foo"), "http://localhost:8080/packages/foo");
+}
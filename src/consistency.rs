@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use diesel::PgConnection;
+use fehler::throws;
+use itertools::Itertools;
+use libflate::gzip;
+use tar::Archive;
+
+use crate::db::get_active_packages_by_repo;
+use crate::db::get_packages;
+use crate::error::Error;
+use crate::parse_pkg_name;
+
+/// A difference between what Postgres says a repository contains and what is
+/// actually on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drift {
+    /// The package is in the repository database, but not active.
+    NotActive { name: String, version: String },
+    /// The package is active, but not in the repository database.
+    NotInDatabase { name: String, version: String },
+    /// The archive or signature of the package does not exist.
+    MissingFile { name: String, version: String, file: String },
+    /// The file in the package directory does not belong to any package.
+    Unreferenced { file: String },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::NotActive { name, version } =>
+                write!(f, "{}-{} is in the database, but not active", name, version),
+            Drift::NotInDatabase { name, version } =>
+                write!(f, "{}-{} is active, but not in the database", name, version),
+            Drift::MissingFile { name, version, file } =>
+                write!(f, "{}-{} refers to missing file {}", name, version, file),
+            Drift::Unreferenced { file } =>
+                write!(f, "{} is not referenced by any package", file),
+        }
+    }
+}
+
+impl Drift {
+    /// Whether rebuilding the repository database fixes this drift.
+    pub fn fixed_by_rebuild(&self) -> bool {
+        match self {
+            Drift::NotActive { .. } | Drift::NotInDatabase { .. } => true,
+            _ => false
+        }
+    }
+}
+
+pub fn repo_db_path(repo_id: i32) -> PathBuf {
+    PathBuf::new()
+        .join("repos")
+        .join(format!("{}.db.tar.gz", repo_id))
+}
+
+/// Compares the database of the repository with the active packages and checks
+/// that the files of these packages exist.
+#[throws]
+pub fn check_repo(conn: &PgConnection, repo_id: i32) -> Vec<Drift> {
+    let db = repo_db_path(repo_id);
+    let in_db = if db.exists() {
+        read_pkgs_from_db(&db)?.into_iter().collect()
+    } else {
+        HashSet::new()
+    };
+
+    let packages = get_active_packages_by_repo(conn, repo_id)?;
+    let active = packages.iter()
+        .map(|p| (p.name.clone(), p.version.clone()))
+        .collect::<HashSet<_>>();
+
+    let mut drift = database_drift(&in_db, &active);
+    for package in &packages {
+        for file in &[&package.archive, &package.signature] {
+            if !Path::new("packages").join(file).exists() {
+                drift.push(Drift::MissingFile {
+                    name: package.name.clone(),
+                    version: package.version.clone(),
+                    file: file.to_string()
+                });
+            }
+        }
+    }
+    drift
+}
+
+/// Compares the packages in the repository database with the active ones, both
+/// given as name and version.
+fn database_drift(in_db: &HashSet<(String, String)>, active: &HashSet<(String, String)>) -> Vec<Drift> {
+    let mut drift = Vec::new();
+    for (name, version) in in_db.difference(active).sorted() {
+        drift.push(Drift::NotActive { name: name.clone(), version: version.clone() });
+    }
+    for (name, version) in active.difference(in_db).sorted() {
+        drift.push(Drift::NotInDatabase { name: name.clone(), version: version.clone() });
+    }
+    drift
+}
+
+/// Returns the files in the package directory that no package refers to.
+/// Files younger than `min_age` are ignored, because they may belong to an
+/// upload that is in progress.
+#[throws]
+pub fn unreferenced_files(conn: &PgConnection, min_age: Duration) -> Vec<Drift> {
+    let referenced = get_packages(conn)?.into_iter()
+        .flat_map(|p| vec![p.archive, p.signature])
+        .collect::<HashSet<_>>();
+
+    let mut drift = Vec::new();
+    for entry in fs::read_dir("packages")? {
+        let entry = entry?;
+        let file = entry.file_name().to_string_lossy().into_owned();
+        if referenced.contains(&file) {
+            continue;
+        }
+        let age = entry.metadata()?.modified()?
+            .elapsed().unwrap_or_default();
+        if age >= min_age {
+            drift.push(Drift::Unreferenced { file });
+        }
+    }
+    drift
+}
+
+/// Lists the packages (name and version) in a repository database.
+#[throws]
+pub fn read_pkgs_from_db(db: impl AsRef<Path>) -> Vec<(String, String)> {
+    let compressed_file = File::open(db)?;
+    let uncompressed_file = gzip::Decoder::new(compressed_file)?;
+    let mut archive = Archive::new(uncompressed_file);
+    archive.entries()?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .map(|entry| entry.path())
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .filter_map(|path| {
+            let components = path.components().collect::<Vec<_>>();
+            if components.len() == 1 {
+                components[0].as_os_str().to_str()
+            } else {
+                None
+            }
+        })
+        .map(parse_pkg_name)
+        .collect::<Result<Vec<_>, _>>()?
+}
+
+/// Writes a repository database that does not contain any packages.
+#[throws]
+pub fn create_empty_db(db: impl AsRef<Path>) {
+    let encoder = gzip::Encoder::new(File::create(db)?)?;
+    let builder = tar::Builder::new(encoder);
+    let encoder = builder.into_inner()?;
+    encoder.finish().into_result()?;
+}
+
+#[test]
+fn test_database_drift() {
+    let set = |packages: &[(&str, &str)]| packages.iter()
+        .map(|(name, version)| (name.to_string(), version.to_string()))
+        .collect::<HashSet<_>>();
+    let in_db = set(&[("a", "1-1"), ("b", "1-1"), ("c", "2-1")]);
+    let active = set(&[("a", "1-1"), ("b", "2-1"), ("d", "1-1")]);
+    let drift = database_drift(&in_db, &active);
+    assert_eq!(drift, vec![
+        Drift::NotActive { name: "b".to_owned(), version: "1-1".to_owned() },
+        Drift::NotActive { name: "c".to_owned(), version: "2-1".to_owned() },
+        Drift::NotInDatabase { name: "b".to_owned(), version: "2-1".to_owned() },
+        Drift::NotInDatabase { name: "d".to_owned(), version: "1-1".to_owned() },
+    ]);
+    assert!(drift.iter().all(|d| d.fixed_by_rebuild()));
+    assert!(database_drift(&in_db, &in_db).is_empty());
+}
+
+#[test]
+fn test_fixed_by_rebuild() {
+    let missing = Drift::MissingFile { name: "a".to_owned(), version: "1-1".to_owned(), file: "a.pkg.tar.zst".to_owned() };
+    assert!(!missing.fixed_by_rebuild());
+    assert!(!Drift::Unreferenced { file: "x".to_owned() }.fixed_by_rebuild());
+    assert_eq!(missing.to_string(), "a-1-1 refers to missing file a.pkg.tar.zst");
+}
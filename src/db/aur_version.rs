@@ -8,23 +8,36 @@ use crate::db::models::NewAurVersion;
 use super::schema;
 
 #[throws]
-pub fn create_aur_version(conn: &PgConnection, package: String, version: String) {
+pub fn create_aur_version(conn: &PgConnection, aur_version: &NewAurVersion) {
     use schema::aur_version::dsl as av;
-    let aur_version = NewAurVersion { package, version };
     diesel::insert_into(av::aur_version)
-        .values(&aur_version)
+        .values(aur_version)
         .on_conflict(av::package)
         .do_update()
-        .set(av::version.eq(&aur_version.version))
+        .set((
+            av::version.eq(&aur_version.version),
+            av::maintainer.eq(&aur_version.maintainer),
+            av::out_of_date.eq(&aur_version.out_of_date),
+            av::last_modified.eq(&aur_version.last_modified),
+            av::last_checked.eq(&aur_version.last_checked)
+        ))
         .execute(conn)?;
 }
 
 #[throws]
-pub fn get_aur_version(conn: &PgConnection, package: &str) -> Option<String> {
+pub fn get_aur_version(conn: &PgConnection, package: &str) -> Option<AurVersion> {
     use schema::aur_version::dsl as av;
     av::aur_version
         .filter(av::package.eq(package))
         .first::<AurVersion>(conn)
         .optional()?
-        .map(|av| av.version)
+}
+
+/// Forgets the packages, e.g. because they were deleted from the AUR.
+#[throws]
+pub fn delete_aur_versions(conn: &PgConnection, packages: &[&str]) {
+    use schema::aur_version::dsl as av;
+    diesel::delete(av::aur_version)
+        .filter(av::package.eq_any(packages.to_vec()))
+        .execute(conn)?;
 }
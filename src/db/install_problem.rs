@@ -0,0 +1,28 @@
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::InstallProblem;
+use crate::db::models::NewInstallProblem;
+
+use super::schema;
+
+#[throws]
+pub fn replace_install_problems(conn: &PgConnection, repo_id: i32, problems: Vec<NewInstallProblem>) {
+    use schema::install_problem::dsl as ip;
+    diesel::delete(ip::install_problem)
+        .filter(ip::repo_id.eq(repo_id))
+        .execute(conn)?;
+    diesel::insert_into(ip::install_problem)
+        .values(problems)
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn get_install_problems_by_package(conn: &PgConnection, package_id: i32) -> Vec<InstallProblem> {
+    use schema::install_problem::dsl as ip;
+    ip::install_problem
+        .filter(ip::package_id.eq(package_id))
+        .order_by((ip::arch.asc(), ip::id.asc()))
+        .load(conn)?
+}
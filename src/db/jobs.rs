@@ -1,21 +1,30 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::IntervalDsl;
+use diesel::dsl::now;
 use diesel::prelude::*;
 use diesel::result::Error;
 use fehler::throws;
 use serde::Serialize;
 
 use crate::db::models::Job;
+use crate::db::models::JobStatus;
 use crate::db::models::NewJob;
 
 use super::schema;
 
+/// How long a claimed job belongs to its worker. Workers renew the lease of
+/// their running jobs well before it expires; the jobs of a worker that died
+/// are put back into the queue once the lease has expired.
+pub const JOB_LEASE_SECONDS: i32 = 60;
+
 #[throws]
-pub fn create_job(conn: &PgConnection, tag: String, job: impl Serialize) {
+pub fn create_job(conn: &PgConnection, tag: String, job: impl Serialize, repo_id: Option<i32>, package_id: Option<i32>) -> Job {
     use schema::job::dsl as j;
     let spec = serde_json::to_value(job).unwrap();
-    let job = NewJob { tag, spec };
+    let job = NewJob { tag, spec, repo_id, package_id };
     diesel::insert_into(j::job)
         .values(job)
-        .execute(conn)?;
+        .get_result(conn)?
 }
 
 #[throws]
@@ -30,7 +39,11 @@ pub fn claim_job(conn: &PgConnection, tag: &str, worker: &str) -> Option<Job> {
         let job = diesel::update(j::job)
             .filter(j::id.eq(job.id))
             .filter(j::worker.is_null())
-            .set(j::worker.eq(worker))
+            .set((
+                j::worker.eq(worker),
+                j::status.eq(JobStatus::Running),
+                j::lease_until.eq((now + JOB_LEASE_SECONDS.seconds()).nullable()),
+                j::updated.eq(now)))
             .get_result(conn)
             .optional()?;
         match job {
@@ -46,11 +59,159 @@ pub fn get_job(conn: &PgConnection, tag: &str) -> Option<Job> {
     j::job
         .filter(j::tag.eq(tag))
         .filter(j::worker.is_null())
+        .filter(j::status.eq(JobStatus::Pending))
+        .filter(j::run_after.is_null().or(j::run_after.le(now)))
+        .order_by(j::id.asc())
         .limit(1)
         .first(conn)
         .optional()?
 }
 
+#[throws]
+pub fn get_job_by_id(conn: &PgConnection, job_id: i32) -> Option<Job> {
+    use schema::job::dsl as j;
+    j::job
+        .filter(j::id.eq(job_id))
+        .first(conn)
+        .optional()?
+}
+
+#[throws]
+pub fn get_jobs_by_package(conn: &PgConnection, package_id: i32) -> Vec<Job> {
+    use schema::job::dsl as j;
+    j::job
+        .filter(j::package_id.eq(package_id))
+        .filter(j::status.ne(JobStatus::Finished))
+        .order_by(j::id.asc())
+        .load(conn)?
+}
+
+#[throws]
+pub fn get_jobs_by_account(conn: &PgConnection, account_id: i32) -> Vec<Job> {
+    use schema::job::dsl as j;
+    use schema::repo::dsl as r;
+    j::job
+        .inner_join(r::repo)
+        .filter(r::owner_id.eq(account_id))
+        .filter(j::status.ne(JobStatus::Finished))
+        .select(schema::job::all_columns)
+        .order_by(j::id.desc())
+        .load(conn)?
+}
+
+/// Returns the jobs that do not belong to any repository, such as the
+/// periodic maintenance tasks.
+#[throws]
+pub fn get_system_jobs(conn: &PgConnection) -> Vec<Job> {
+    use schema::job::dsl as j;
+    j::job
+        .filter(j::repo_id.is_null())
+        .filter(j::status.ne(JobStatus::Finished))
+        .order_by(j::id.desc())
+        .load(conn)?
+}
+
+/// Returns whether there is a job with the given tag that is either waiting
+/// for a worker or currently being worked on.
+#[throws]
+pub fn has_open_job(conn: &PgConnection, tag: &str) -> bool {
+    use schema::job::dsl as j;
+    let count = j::job
+        .filter(j::tag.eq(tag))
+        .filter(j::status.ne(JobStatus::Finished))
+        .filter(j::status.ne(JobStatus::Failed))
+        .count()
+        .first::<i64>(conn)?;
+    count > 0
+}
+
+#[throws]
+pub fn finish_job(conn: &PgConnection, job_id: i32) {
+    use schema::job::dsl as j;
+    diesel::update(j::job)
+        .filter(j::id.eq(job_id))
+        .set((
+            j::status.eq(JobStatus::Finished),
+            j::updated.eq(now)))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn fail_job(conn: &PgConnection, job_id: i32, error: &str) {
+    use schema::job::dsl as j;
+    diesel::update(j::job)
+        .filter(j::id.eq(job_id))
+        .set((
+            j::status.eq(JobStatus::Failed),
+            j::error.eq(error),
+            j::updated.eq(now)))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn retry_job(conn: &PgConnection, job_id: i32) {
+    use schema::job::dsl as j;
+    diesel::update(j::job)
+        .filter(j::id.eq(job_id))
+        .filter(j::status.eq(JobStatus::Failed))
+        .set((
+            j::status.eq(JobStatus::Pending),
+            j::worker.eq(None::<String>),
+            j::error.eq(None::<String>),
+            j::updated.eq(now)))
+        .execute(conn)?;
+}
+
+/// Puts the job back into the queue, to be claimed again after the given time.
+#[throws]
+pub fn reschedule_job(conn: &PgConnection, job_id: i32, run_after: NaiveDateTime) {
+    use schema::job::dsl as j;
+    diesel::update(j::job)
+        .filter(j::id.eq(job_id))
+        .set((
+            j::status.eq(JobStatus::Pending),
+            j::worker.eq(None::<String>),
+            j::run_after.eq(run_after),
+            j::updated.eq(now)))
+        .execute(conn)?;
+}
+
+/// Extends the lease of the running jobs of the worker.
+#[throws]
+pub fn renew_job_leases(conn: &PgConnection, worker: &str) -> usize {
+    use schema::job::dsl as j;
+    diesel::update(j::job)
+        .filter(j::worker.eq(worker))
+        .filter(j::status.eq(JobStatus::Running))
+        .set(j::lease_until.eq((now + JOB_LEASE_SECONDS.seconds()).nullable()))
+        .execute(conn)?
+}
+
+/// Puts the running jobs whose lease has expired back into the queue.
+#[throws]
+pub fn release_jobs(conn: &PgConnection, tag: &str) -> usize {
+    use schema::job::dsl as j;
+    diesel::update(j::job)
+        .filter(j::tag.eq(tag))
+        .filter(j::status.eq(JobStatus::Running))
+        .filter(j::lease_until.lt(now.nullable()).or(j::lease_until.is_null()))
+        .set((
+            j::status.eq(JobStatus::Pending),
+            j::worker.eq(None::<String>),
+            j::lease_until.eq(None::<NaiveDateTime>),
+            j::updated.eq(now)))
+        .execute(conn)?
+}
+
+#[throws]
+pub fn delete_finished_jobs(conn: &PgConnection, finished_before: NaiveDateTime) -> usize {
+    use schema::job::dsl as j;
+    diesel::delete(j::job)
+        .filter(j::status.eq(JobStatus::Finished))
+        .filter(j::updated.lt(finished_before))
+        .execute(conn)?
+}
+
 #[throws]
 pub fn delete_job(conn: &PgConnection, job_id: i32) {
     use schema::job::dsl as j;
@@ -2,16 +2,15 @@ use diesel::prelude::*;
 use diesel::result::Error;
 use fehler::throws;
 
+use crate::db::models::MissingDep;
 use crate::db::models::NewMissingDep;
+use crate::db::models::Package;
 
 use super::schema;
 
 #[throws]
-pub fn replace_missing_deps(conn: &PgConnection, repo_id: i32, missing_deps: Vec<String>) {
+pub fn replace_missing_deps(conn: &PgConnection, repo_id: i32, missing_deps: Vec<NewMissingDep>) {
     use schema::missing_dep::dsl as md;
-    let missing_deps = missing_deps.into_iter()
-        .map(|dependency| NewMissingDep { repo_id, dependency })
-        .collect::<Vec<_>>();
     diesel::delete(md::missing_dep)
         .filter(md::repo_id.eq(repo_id))
         .execute(conn)?;
@@ -21,10 +20,21 @@ pub fn replace_missing_deps(conn: &PgConnection, repo_id: i32, missing_deps: Vec
 }
 
 #[throws]
-pub fn get_missing_deps(conn: &PgConnection, repo_id: i32) -> Vec<String> {
+pub fn get_missing_deps(conn: &PgConnection, repo_id: i32) -> Vec<(MissingDep, Package)> {
     use schema::missing_dep::dsl as md;
+    use schema::package::dsl as p;
     md::missing_dep
+        .inner_join(p::package)
         .filter(md::repo_id.eq(repo_id))
-        .select(md::dependency)
+        .order_by((md::dependency.asc(), md::arch.asc(), p::name.asc()))
+        .load(conn)?
+}
+
+#[throws]
+pub fn get_missing_deps_by_package(conn: &PgConnection, package_id: i32) -> Vec<MissingDep> {
+    use schema::missing_dep::dsl as md;
+    md::missing_dep
+        .filter(md::package_id.eq(package_id))
+        .order_by((md::dependency.asc(), md::arch.asc()))
         .load(conn)?
 }
@@ -1,3 +1,5 @@
+use std::cmp::max;
+
 use diesel::QueryResult;
 use diesel::result::DatabaseErrorKind::UniqueViolation;
 use diesel::result::Error::DatabaseError;
@@ -5,26 +7,44 @@ use serde::Serialize;
 
 pub use account::*;
 pub use aur_version::*;
+pub use install_problem::*;
 pub use jobs::*;
 pub use missing_deps::*;
+pub use needed_rebuild::*;
+pub use notification::*;
 pub use package::*;
 pub use package_depends::*;
+pub use package_event::*;
 pub use package_provides::*;
+pub use periodic_task::*;
 pub use repo::*;
+pub use repo_layer::*;
 pub use token::*;
+pub use upstream_collision::*;
+pub use version_source::*;
+pub use webhook::*;
 
 mod schema;
 pub mod models;
 
 mod account;
 mod aur_version;
+mod install_problem;
 mod jobs;
 mod missing_deps;
+mod needed_rebuild;
+mod notification;
 mod package;
 mod package_depends;
+mod package_event;
 mod package_provides;
+mod periodic_task;
 mod repo;
+mod repo_layer;
 mod token;
+mod upstream_collision;
+mod version_source;
+mod webhook;
 
 pub trait ExpectConflict {
     type Output;
@@ -52,10 +72,20 @@ pub struct Paginated<T> {
 
 impl<T> Paginated<T> {
     pub fn new(items: Vec<T>, total_items: usize, current_page: usize, page_size: usize) -> Self {
-        let total_pages = total_items / page_size + 1;
+        let total_pages = max((total_items + page_size - 1) / page_size, 1);
         Paginated { items, total_items, current_page, total_pages }
     }
 
+    /// Returns a page of items that were loaded all at once.
+    pub fn slice(items: Vec<T>, current_page: usize, page_size: usize) -> Self {
+        let total_items = items.len();
+        let items = items.into_iter()
+            .skip(current_page * page_size)
+            .take(page_size)
+            .collect();
+        Paginated::new(items, total_items, current_page, page_size)
+    }
+
     pub fn try_map<U, F: Fn(T) -> Result<U, E>, E>(self, f: F) -> Result<Paginated<U>, E> {
         let items = self.items.into_iter().map(f).collect::<Result<Vec<U>, E>>()?;
         let paginated = Paginated {
@@ -67,3 +97,12 @@ impl<T> Paginated<T> {
         Ok(paginated)
     }
 }
+
+#[test]
+fn test_paginated() {
+    let page = Paginated::slice((0..250).collect::<Vec<_>>(), 2, 100);
+    assert_eq!(page.items, (200..250).collect::<Vec<_>>());
+    assert_eq!((page.total_items, page.current_page, page.total_pages), (250, 2, 3));
+    assert_eq!(Paginated::slice(vec![0; 200], 0, 100).total_pages, 2);
+    assert_eq!(Paginated::<i32>::slice(vec![], 0, 100).total_pages, 1);
+}
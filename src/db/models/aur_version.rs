@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use serde::Serialize;
 
 use crate::db::schema::*;
@@ -6,12 +7,20 @@ use crate::db::schema::*;
 pub struct AurVersion {
     pub id: i32,
     pub package: String,
-    pub version: String
+    pub version: String,
+    pub maintainer: Option<String>,
+    pub out_of_date: Option<NaiveDateTime>,
+    pub last_modified: Option<NaiveDateTime>,
+    pub last_checked: NaiveDateTime
 }
 
 #[derive(Debug, Serialize, Insertable)]
 #[table_name="aur_version"]
 pub struct NewAurVersion {
     pub package: String,
-    pub version: String
+    pub version: String,
+    pub maintainer: Option<String>,
+    pub out_of_date: Option<NaiveDateTime>,
+    pub last_modified: Option<NaiveDateTime>,
+    pub last_checked: NaiveDateTime
 }
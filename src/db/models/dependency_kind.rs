@@ -0,0 +1,75 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// The field of the PKGINFO that a dependency comes from, which is also how
+/// the kind is written. Only `Depend` is needed to install a package;
+/// `MakeDepend` and `CheckDepend` are needed to build it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, FromSqlRow, AsExpression)]
+#[serde(rename_all = "lowercase")]
+#[sql_type = "Text"]
+pub enum DependencyKind {
+    Depend, OptDepend, MakeDepend, CheckDepend
+}
+
+impl DependencyKind {
+    pub const ALL: &'static [DependencyKind] = &[
+        DependencyKind::Depend,
+        DependencyKind::OptDepend,
+        DependencyKind::MakeDepend,
+        DependencyKind::CheckDepend
+    ];
+}
+
+impl FromStr for DependencyKind {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        match string {
+            "depend" => DependencyKind::Depend,
+            "optdepend" => DependencyKind::OptDepend,
+            "makedepend" => DependencyKind::MakeDepend,
+            "checkdepend" => DependencyKind::CheckDepend,
+            _ => Err(format!("Unknown dependency kind {}", string))?
+        }
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            DependencyKind::Depend => "depend",
+            DependencyKind::OptDepend => "optdepend",
+            DependencyKind::MakeDepend => "makedepend",
+            DependencyKind::CheckDepend => "checkdepend",
+        };
+        write!(fmt, "{}", repr)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for DependencyKind
+    where DB: Backend, String: FromSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn from_sql(bytes: Option<&DB::RawValue>) -> Self {
+        String::from_sql(bytes)?.parse()?
+    }
+}
+
+impl<DB> ToSql<Text, DB> for DependencyKind
+    where DB: Backend, String: ToSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> IsNull {
+        self.to_string().to_sql(out)?
+    }
+}
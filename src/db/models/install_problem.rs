@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct InstallProblem {
+    pub id: i32,
+    pub repo_id: i32,
+    pub package_id: i32,
+    pub arch: String,
+    pub kind: String,
+    pub message: String
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="install_problem"]
+pub struct NewInstallProblem {
+    pub repo_id: i32,
+    pub package_id: i32,
+    pub arch: String,
+    pub kind: String,
+    pub message: String
+}
@@ -1,18 +1,34 @@
+use chrono::NaiveDateTime;
 use serde::Serialize;
 
 use crate::db::schema::*;
 
+use super::JobStatus;
+
 #[derive(Debug, Serialize, Queryable)]
 pub struct Job {
     pub id: i32,
     pub tag: String,
     pub spec: serde_json::Value,
-    pub worker: Option<String>
+    pub worker: Option<String>,
+    pub status: JobStatus,
+    pub error: Option<String>,
+    pub created: NaiveDateTime,
+    pub updated: NaiveDateTime,
+    pub repo_id: Option<i32>,
+    pub package_id: Option<i32>,
+    /// Pending jobs are not claimed before this time.
+    pub run_after: Option<NaiveDateTime>,
+    /// A running job is put back into the queue once this time has passed
+    /// without the worker renewing the lease.
+    pub lease_until: Option<NaiveDateTime>
 }
 
 #[derive(Debug, Serialize, Insertable)]
 #[table_name="job"]
 pub struct NewJob {
     pub tag: String,
-    pub spec: serde_json::Value
+    pub spec: serde_json::Value,
+    pub repo_id: Option<i32>,
+    pub package_id: Option<i32>
 }
@@ -0,0 +1,63 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, FromSqlRow, AsExpression)]
+#[serde(rename_all = "lowercase")]
+#[sql_type = "Text"]
+pub enum JobStatus {
+    Pending, Running, Finished, Failed
+}
+
+impl FromStr for JobStatus {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        match string {
+            "pending" => JobStatus::Pending,
+            "running" => JobStatus::Running,
+            "finished" => JobStatus::Finished,
+            "failed" => JobStatus::Failed,
+            _ => Err(format!("Unknown job status {}", string))?
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Finished => "finished",
+            JobStatus::Failed => "failed",
+        };
+        write!(fmt, "{}", repr)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for JobStatus
+    where DB: Backend, String: FromSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn from_sql(bytes: Option<&DB::RawValue>) -> Self {
+        String::from_sql(bytes)?.parse()?
+    }
+}
+
+impl<DB> ToSql<Text, DB> for JobStatus
+    where DB: Backend, String: ToSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> IsNull {
+        self.to_string().to_sql(out)?
+    }
+}
@@ -1,15 +1,23 @@
 use crate::db::schema::*;
 
+use super::DependencyKind;
+
 #[derive(Debug, Queryable)]
 pub struct MissingDep {
     pub id: i32,
     pub repo_id: i32,
-    pub dependency: String
+    pub dependency: String,
+    pub arch: String,
+    pub package_id: i32,
+    pub kind: DependencyKind
 }
 
 #[derive(Debug, Insertable)]
 #[table_name="missing_dep"]
 pub struct NewMissingDep {
     pub repo_id: i32,
-    pub dependency: String
+    pub dependency: String,
+    pub arch: String,
+    pub package_id: i32,
+    pub kind: DependencyKind
 }
@@ -1,22 +1,56 @@
 pub use account::*;
 pub use aur_version::*;
 pub use compression::*;
+pub use dependency_kind::*;
+pub use install_problem::*;
 pub use job::*;
+pub use job_status::*;
 pub use missing_dep::*;
+pub use needed_rebuild::*;
+pub use notification::*;
+pub use notification_kind::*;
+pub use notification_preference::*;
 pub use package::*;
 pub use package_depends::*;
+pub use package_event::*;
+pub use package_event_kind::*;
 pub use package_provides::*;
+pub use periodic_task::*;
 pub use repo::*;
+pub use repo_layer::*;
 pub use token::*;
+pub use upstream_collision::*;
+pub use version_source::*;
+pub use version_source_kind::*;
+pub use webhook::*;
+pub use webhook_delivery::*;
+pub use webhook_event::*;
 
 mod account;
 mod aur_version;
 mod compression;
+mod dependency_kind;
+mod install_problem;
 mod job;
+mod job_status;
 mod missing_dep;
+mod needed_rebuild;
+mod notification;
+mod notification_kind;
+mod notification_preference;
 mod package;
 mod package_depends;
+mod package_event;
+mod package_event_kind;
 mod package_provides;
+mod periodic_task;
 mod repo;
+mod repo_layer;
 mod token;
+mod upstream_collision;
+mod version_source;
+mod version_source_kind;
+mod webhook;
+mod webhook_delivery;
+mod webhook_event;
 
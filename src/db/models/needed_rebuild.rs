@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct NeededRebuild {
+    pub id: i32,
+    pub repo_id: i32,
+    pub package_id: i32,
+    pub arch: String,
+    pub dependency: String,
+    pub available: String
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="needed_rebuild"]
+pub struct NewNeededRebuild {
+    pub repo_id: i32,
+    pub package_id: i32,
+    pub arch: String,
+    pub dependency: String,
+    pub available: String
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+use super::NotificationKind;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Notification {
+    pub id: i32,
+    pub account_id: i32,
+    pub kind: NotificationKind,
+    pub message: String,
+    pub created: NaiveDateTime,
+    /// When the notification was sent as part of a digest, if it was.
+    pub sent: Option<NaiveDateTime>
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="notification"]
+pub struct NewNotification {
+    pub account_id: i32,
+    pub kind: NotificationKind,
+    pub message: String
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// What a notification is about. Accounts can turn off every kind separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, FromSqlRow, AsExpression)]
+#[serde(rename_all = "snake_case")]
+#[sql_type = "Text"]
+pub enum NotificationKind {
+    Outdated, MissingDeps, FailedJob
+}
+
+impl FromStr for NotificationKind {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        match string {
+            "outdated" => NotificationKind::Outdated,
+            "missing_deps" => NotificationKind::MissingDeps,
+            "failed_job" => NotificationKind::FailedJob,
+            _ => Err(format!("Unknown notification kind {}", string))?
+        }
+    }
+}
+
+impl fmt::Display for NotificationKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            NotificationKind::Outdated => "outdated",
+            NotificationKind::MissingDeps => "missing_deps",
+            NotificationKind::FailedJob => "failed_job",
+        };
+        write!(fmt, "{}", repr)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for NotificationKind
+    where DB: Backend, String: FromSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn from_sql(bytes: Option<&DB::RawValue>) -> Self {
+        String::from_sql(bytes)?.parse()?
+    }
+}
+
+impl<DB> ToSql<Text, DB> for NotificationKind
+    where DB: Backend, String: ToSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> IsNull {
+        self.to_string().to_sql(out)?
+    }
+}
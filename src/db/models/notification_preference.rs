@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+use super::NotificationKind;
+
+/// Where an account wants to be notified, and about what. Accounts without
+/// preferences are not notified at all, and neither are accounts that have not
+/// confirmed their email address yet.
+#[derive(Debug, Serialize, Queryable, Insertable)]
+#[table_name="notification_preference"]
+pub struct NotificationPreference {
+    pub account_id: i32,
+    pub email: String,
+    pub outdated: bool,
+    pub missing_deps: bool,
+    pub failed_jobs: bool,
+    pub confirmed: bool,
+    /// The token of the link that confirms the email address.
+    #[serde(skip)]
+    pub confirmation_token: Option<String>,
+    /// When the link was sent. Links that have not been sent yet are sent by
+    /// the `send_notifications` task.
+    pub confirmation_sent: Option<NaiveDateTime>
+}
+
+impl NotificationPreference {
+    pub fn wants(&self, kind: NotificationKind) -> bool {
+        self.confirmed && match kind {
+            NotificationKind::Outdated => self.outdated,
+            NotificationKind::MissingDeps => self.missing_deps,
+            NotificationKind::FailedJob => self.failed_jobs
+        }
+    }
+}
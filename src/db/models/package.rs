@@ -18,7 +18,8 @@ pub struct Package {
     pub created: NaiveDateTime,
     pub active: bool,
     pub deleted: bool,
-    pub repo_id: i32
+    pub repo_id: i32,
+    pub description: Option<String>
 }
 
 #[derive(Debug, Serialize, Insertable)]
@@ -31,5 +32,6 @@ pub struct NewPackage {
     pub archive: String,
     pub signature: String,
     pub compression: Compression,
-    pub repo_id: i32
+    pub repo_id: i32,
+    pub description: Option<String>
 }
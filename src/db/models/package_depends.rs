@@ -1,15 +1,19 @@
 use crate::db::schema::*;
 
+use super::DependencyKind;
+
 #[derive(Debug, Queryable)]
 pub struct PackageDepends {
     pub id: i32,
     pub package_id: i32,
-    pub depends: String
+    pub depends: String,
+    pub kind: DependencyKind
 }
 
 #[derive(Debug, Insertable)]
 #[table_name="package_depends"]
 pub struct NewPackageDepends {
     pub package_id: i32,
-    pub depends: String
+    pub depends: String,
+    pub kind: DependencyKind
 }
@@ -0,0 +1,45 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+use super::Package;
+use super::PackageEventKind;
+
+/// A package that was uploaded to or removed from a repository. Unlike the
+/// package itself, the event is kept after the package is removed.
+#[derive(Debug, Serialize, Queryable)]
+pub struct PackageEvent {
+    pub id: i32,
+    pub repo_id: i32,
+    pub kind: PackageEventKind,
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub description: Option<String>,
+    pub created: NaiveDateTime
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="package_event"]
+pub struct NewPackageEvent {
+    pub repo_id: i32,
+    pub kind: PackageEventKind,
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub description: Option<String>
+}
+
+impl NewPackageEvent {
+    pub fn new(package: &Package, kind: PackageEventKind) -> NewPackageEvent {
+        NewPackageEvent {
+            repo_id: package.repo_id,
+            kind,
+            name: package.name.clone(),
+            version: package.version.clone(),
+            arch: package.arch.clone(),
+            description: package.description.clone()
+        }
+    }
+}
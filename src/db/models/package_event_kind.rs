@@ -0,0 +1,59 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, FromSqlRow, AsExpression)]
+#[serde(rename_all = "snake_case")]
+#[sql_type = "Text"]
+pub enum PackageEventKind {
+    Uploaded, Removed
+}
+
+impl FromStr for PackageEventKind {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        match string {
+            "uploaded" => PackageEventKind::Uploaded,
+            "removed" => PackageEventKind::Removed,
+            _ => Err(format!("Unknown package event {}", string))?
+        }
+    }
+}
+
+impl fmt::Display for PackageEventKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            PackageEventKind::Uploaded => "uploaded",
+            PackageEventKind::Removed => "removed",
+        };
+        write!(fmt, "{}", repr)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for PackageEventKind
+    where DB: Backend, String: FromSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn from_sql(bytes: Option<&DB::RawValue>) -> Self {
+        String::from_sql(bytes)?.parse()?
+    }
+}
+
+impl<DB> ToSql<Text, DB> for PackageEventKind
+    where DB: Backend, String: ToSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> IsNull {
+        self.to_string().to_sql(out)?
+    }
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct PeriodicTask {
+    pub id: i32,
+    pub name: String,
+    pub period: i32,
+    pub last_run: Option<NaiveDateTime>,
+    pub next_run: NaiveDateTime
+}
+
+#[derive(Debug, Serialize, Insertable)]
+#[table_name="periodic_task"]
+pub struct NewPeriodicTask {
+    pub name: String,
+    pub period: i32
+}
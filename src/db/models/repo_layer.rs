@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+/// Declares that the packages of the base repository may satisfy the
+/// dependencies of the packages in the repository.
+#[derive(Debug, Serialize, Queryable)]
+pub struct RepoLayer {
+    pub id: i32,
+    pub repo_id: i32,
+    pub base_repo_id: i32
+}
+
+#[derive(Debug, Serialize, Insertable)]
+#[table_name="repo_layer"]
+pub struct NewRepoLayer {
+    pub repo_id: i32,
+    pub base_repo_id: i32
+}
@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct UpstreamCollision {
+    pub id: i32,
+    pub repo_id: i32,
+    pub package_id: i32,
+    pub arch: String,
+    pub upstream_repo: String,
+    pub upstream_version: String
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="upstream_collision"]
+pub struct NewUpstreamCollision {
+    pub repo_id: i32,
+    pub package_id: i32,
+    pub arch: String,
+    pub upstream_repo: String,
+    pub upstream_version: String
+}
@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+use super::VersionSourceKind;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct VersionSource {
+    pub id: i32,
+    pub repo_id: i32,
+    pub package: String,
+    pub kind: VersionSourceKind,
+    pub source: String,
+    pub pattern: Option<String>,
+    /// The latest version found by the last check, if it succeeded.
+    pub version: Option<String>,
+    /// Why the last check failed.
+    pub error: Option<String>,
+    pub last_checked: Option<NaiveDateTime>
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="version_source"]
+pub struct NewVersionSource {
+    pub repo_id: i32,
+    pub package: String,
+    pub kind: VersionSourceKind,
+    pub source: String,
+    pub pattern: Option<String>
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// Where the latest upstream version of a package is looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, FromSqlRow, AsExpression)]
+#[serde(rename_all = "lowercase")]
+#[sql_type = "Text"]
+pub enum VersionSourceKind {
+    /// The releases of a GitHub repository, given as `owner/repo`.
+    GitHub,
+    /// The releases of a GitLab project, given by its URL.
+    GitLab,
+    /// The tags of a git repository, given by its URL.
+    Git,
+    /// The matches of the pattern on a web page, given by its URL.
+    Regex,
+    /// The output of a shell command on the host that checks the versions.
+    Command
+}
+
+impl FromStr for VersionSourceKind {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        match string {
+            "github" => VersionSourceKind::GitHub,
+            "gitlab" => VersionSourceKind::GitLab,
+            "git" => VersionSourceKind::Git,
+            "regex" => VersionSourceKind::Regex,
+            "command" => VersionSourceKind::Command,
+            _ => Err(format!("Unknown version source {}", string))?
+        }
+    }
+}
+
+impl fmt::Display for VersionSourceKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            VersionSourceKind::GitHub => "github",
+            VersionSourceKind::GitLab => "gitlab",
+            VersionSourceKind::Git => "git",
+            VersionSourceKind::Regex => "regex",
+            VersionSourceKind::Command => "command",
+        };
+        write!(fmt, "{}", repr)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for VersionSourceKind
+    where DB: Backend, String: FromSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn from_sql(bytes: Option<&DB::RawValue>) -> Self {
+        String::from_sql(bytes)?.parse()?
+    }
+}
+
+impl<DB> ToSql<Text, DB> for VersionSourceKind
+    where DB: Backend, String: ToSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> IsNull {
+        self.to_string().to_sql(out)?
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Webhook {
+    pub id: i32,
+    pub repo_id: i32,
+    pub url: String,
+    /// The key that payloads are signed with.
+    pub secret: String,
+    pub created: NaiveDateTime
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="webhook"]
+pub struct NewWebhook {
+    pub repo_id: i32,
+    pub url: String,
+    pub secret: String
+}
@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::schema::*;
+
+use super::WebhookEvent;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: WebhookEvent,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    /// The status code of the last attempt, if the server responded.
+    pub status_code: Option<i32>,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub created: NaiveDateTime,
+    pub last_attempt: Option<NaiveDateTime>,
+    pub delivered: Option<NaiveDateTime>
+}
+
+#[derive(Debug, Insertable)]
+#[table_name="webhook_delivery"]
+pub struct NewWebhookDelivery {
+    pub webhook_id: i32,
+    pub event: WebhookEvent,
+    pub payload: serde_json::Value
+}
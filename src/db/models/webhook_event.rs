@@ -0,0 +1,66 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// What happened in a repository that webhooks are told about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, FromSqlRow, AsExpression)]
+#[serde(rename_all = "snake_case")]
+#[sql_type = "Text"]
+pub enum WebhookEvent {
+    PackageUploaded, PackageActivated, PackageRemoved, MissingDepsChanged, UpstreamOutdated
+}
+
+impl FromStr for WebhookEvent {
+    type Err = Error;
+    #[throws]
+    fn from_str(string: &str) -> Self {
+        match string {
+            "package_uploaded" => WebhookEvent::PackageUploaded,
+            "package_activated" => WebhookEvent::PackageActivated,
+            "package_removed" => WebhookEvent::PackageRemoved,
+            "missing_deps_changed" => WebhookEvent::MissingDepsChanged,
+            "upstream_outdated" => WebhookEvent::UpstreamOutdated,
+            _ => Err(format!("Unknown webhook event {}", string))?
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            WebhookEvent::PackageUploaded => "package_uploaded",
+            WebhookEvent::PackageActivated => "package_activated",
+            WebhookEvent::PackageRemoved => "package_removed",
+            WebhookEvent::MissingDepsChanged => "missing_deps_changed",
+            WebhookEvent::UpstreamOutdated => "upstream_outdated",
+        };
+        write!(fmt, "{}", repr)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for WebhookEvent
+    where DB: Backend, String: FromSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn from_sql(bytes: Option<&DB::RawValue>) -> Self {
+        String::from_sql(bytes)?.parse()?
+    }
+}
+
+impl<DB> ToSql<Text, DB> for WebhookEvent
+    where DB: Backend, String: ToSql<Text, DB>,
+{
+    #[throws(Box<dyn std::error::Error + Send + Sync>)]
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> IsNull {
+        self.to_string().to_sql(out)?
+    }
+}
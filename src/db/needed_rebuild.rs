@@ -0,0 +1,40 @@
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NeededRebuild;
+use crate::db::models::NewNeededRebuild;
+use crate::db::models::Package;
+
+use super::schema;
+
+#[throws]
+pub fn replace_needed_rebuilds(conn: &PgConnection, repo_id: i32, rebuilds: Vec<NewNeededRebuild>) {
+    use schema::needed_rebuild::dsl as nr;
+    diesel::delete(nr::needed_rebuild)
+        .filter(nr::repo_id.eq(repo_id))
+        .execute(conn)?;
+    diesel::insert_into(nr::needed_rebuild)
+        .values(rebuilds)
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn get_needed_rebuilds(conn: &PgConnection, repo_id: i32) -> Vec<(NeededRebuild, Package)> {
+    use schema::needed_rebuild::dsl as nr;
+    use schema::package::dsl as p;
+    nr::needed_rebuild
+        .inner_join(p::package)
+        .filter(nr::repo_id.eq(repo_id))
+        .order_by((p::name.asc(), nr::arch.asc(), nr::dependency.asc()))
+        .load(conn)?
+}
+
+#[throws]
+pub fn get_needed_rebuilds_by_package(conn: &PgConnection, package_id: i32) -> Vec<NeededRebuild> {
+    use schema::needed_rebuild::dsl as nr;
+    nr::needed_rebuild
+        .filter(nr::package_id.eq(package_id))
+        .order_by((nr::arch.asc(), nr::dependency.asc()))
+        .load(conn)?
+}
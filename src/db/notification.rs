@@ -0,0 +1,124 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewNotification;
+use crate::db::models::Notification;
+use crate::db::models::NotificationPreference;
+
+use super::schema;
+
+#[throws]
+pub fn get_notification_preference(conn: &PgConnection, account_id: i32) -> Option<NotificationPreference> {
+    use schema::notification_preference::dsl as np;
+    np::notification_preference
+        .filter(np::account_id.eq(account_id))
+        .first(conn)
+        .optional()?
+}
+
+#[throws]
+pub fn set_notification_preference(conn: &PgConnection, preference: &NotificationPreference) {
+    use schema::notification_preference::dsl as np;
+    diesel::insert_into(np::notification_preference)
+        .values(preference)
+        .on_conflict(np::account_id)
+        .do_update()
+        .set((
+            np::email.eq(&preference.email),
+            np::outdated.eq(preference.outdated),
+            np::missing_deps.eq(preference.missing_deps),
+            np::failed_jobs.eq(preference.failed_jobs),
+            np::confirmed.eq(preference.confirmed),
+            np::confirmation_token.eq(&preference.confirmation_token),
+            np::confirmation_sent.eq(preference.confirmation_sent)
+        ))
+        .execute(conn)?;
+}
+
+/// Returns the preferences whose confirmation link has not been sent yet.
+#[throws]
+pub fn get_unconfirmed_notification_preferences(conn: &PgConnection) -> Vec<NotificationPreference> {
+    use schema::notification_preference::dsl as np;
+    np::notification_preference
+        .filter(np::confirmed.eq(false))
+        .filter(np::confirmation_sent.is_null())
+        .load(conn)?
+}
+
+#[throws]
+pub fn set_confirmation_token(conn: &PgConnection, account_id: i32, token: &str) {
+    use schema::notification_preference::dsl as np;
+    diesel::update(np::notification_preference)
+        .filter(np::account_id.eq(account_id))
+        .set(np::confirmation_token.eq(token))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn set_confirmation_sent(conn: &PgConnection, account_id: i32, sent: NaiveDateTime) {
+    use schema::notification_preference::dsl as np;
+    diesel::update(np::notification_preference)
+        .filter(np::account_id.eq(account_id))
+        .set(np::confirmation_sent.eq(sent))
+        .execute(conn)?;
+}
+
+/// Confirms the email address that the token was sent to. The token is used
+/// up. Returns whether the token is known.
+#[throws]
+pub fn confirm_notification_preference(conn: &PgConnection, token: &str) -> bool {
+    use schema::notification_preference::dsl as np;
+    let updated = diesel::update(np::notification_preference)
+        .filter(np::confirmation_token.eq(token))
+        .set((np::confirmed.eq(true), np::confirmation_token.eq(None::<String>)))
+        .execute(conn)?;
+    updated > 0
+}
+
+/// Turns off notifications for the account. Notifications that are still
+/// queued are not sent anymore.
+#[throws]
+pub fn delete_notification_preference(conn: &PgConnection, account_id: i32) {
+    use schema::notification_preference::dsl as np;
+    diesel::delete(np::notification_preference)
+        .filter(np::account_id.eq(account_id))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn create_notification(conn: &PgConnection, notification: &NewNotification) {
+    use schema::notification::dsl as n;
+    diesel::insert_into(n::notification)
+        .values(notification)
+        .execute(conn)?;
+}
+
+/// Returns the notifications that have not been sent yet, grouped by account
+/// and in the order they were created.
+#[throws]
+pub fn get_unsent_notifications(conn: &PgConnection) -> Vec<Notification> {
+    use schema::notification::dsl as n;
+    n::notification
+        .filter(n::sent.is_null())
+        .order_by((n::account_id.asc(), n::id.asc()))
+        .load(conn)?
+}
+
+#[throws]
+pub fn set_notifications_sent(conn: &PgConnection, ids: &[i32], sent: NaiveDateTime) {
+    use schema::notification::dsl as n;
+    diesel::update(n::notification)
+        .filter(n::id.eq_any(ids.to_vec()))
+        .set(n::sent.eq(sent))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn delete_sent_notifications(conn: &PgConnection, sent_before: NaiveDateTime) -> usize {
+    use schema::notification::dsl as n;
+    diesel::delete(n::notification)
+        .filter(n::sent.lt(sent_before))
+        .execute(conn)?
+}
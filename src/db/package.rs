@@ -7,6 +7,7 @@ use fehler::throws;
 
 use crate::db::models::NewPackage;
 use crate::db::models::Package;
+use crate::db::models::Repo;
 use crate::db::package_depends::delete_package_depends;
 use crate::db::package_provides::delete_package_provides;
 use crate::db::Paginated;
@@ -44,6 +45,17 @@ pub fn get_all_packages_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<Packag
         .load(conn)?
 }
 
+/// Returns the packages of the repository that are supposed to be in its
+/// database, including those that are deleted but not yet removed from it.
+#[throws]
+pub fn get_active_packages_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<Package> {
+    use schema::package::dsl as p;
+    p::package
+        .filter(p::repo_id.eq(repo_id))
+        .filter(p::active.eq(true))
+        .load(conn)?
+}
+
 #[throws]
 pub fn get_packages_by_repo(conn: &PgConnection, repo_id: i32, page: usize) -> Paginated<Package> {
     use schema::package::dsl as p;
@@ -94,6 +106,21 @@ pub fn get_package_by_repo(conn: &PgConnection, repo_id: i32, name: &str, versio
         .optional()?
 }
 
+/// Returns the active packages with the given name in every repository,
+/// together with their repository.
+#[throws]
+pub fn get_active_packages_by_name(conn: &PgConnection, name: &str) -> Vec<(Package, Repo)> {
+    use schema::package::dsl as p;
+    use schema::repo::dsl as r;
+    p::package
+        .inner_join(r::repo)
+        .filter(p::name.eq(name))
+        .filter(p::active.eq(true))
+        .filter(p::deleted.eq(false))
+        .order_by(p::id.asc())
+        .load(conn)?
+}
+
 #[throws]
 pub fn get_packages_by_query(conn: &PgConnection, query: &str) -> Vec<Package> {
     use schema::package::dsl as p;
@@ -123,6 +150,15 @@ pub fn set_package_deleted(conn: &PgConnection, id: i32, deleted: bool) {
         .execute(conn)?;
 }
 
+#[throws]
+pub fn set_package_inactive(conn: &PgConnection, id: i32) {
+    use schema::package::dsl as p;
+    diesel::update(p::package)
+        .filter(p::id.eq(id))
+        .set(p::active.eq(false))
+        .execute(conn)?;
+}
+
 #[throws]
 pub fn set_package_active(conn: &PgConnection, id: i32) {
     let query = "\
@@ -3,14 +3,14 @@ use diesel::prelude::*;
 use diesel::result::Error;
 use fehler::throws;
 
-use crate::db::models::{NewPackageDepends, PackageDepends};
+use crate::db::models::{DependencyKind, NewPackageDepends, PackageDepends};
 
 use super::schema;
 
 #[throws]
-pub fn create_package_depends(conn: &PgConnection, package_id: i32, depends: String) {
+pub fn create_package_depends(conn: &PgConnection, package_id: i32, depends: String, kind: DependencyKind) {
     use schema::package_depends::dsl as pv;
-    let package_depends = NewPackageDepends { package_id, depends };
+    let package_depends = NewPackageDepends { package_id, depends, kind };
     diesel::insert_into(pv::package_depends)
         .values(package_depends)
         .execute(conn)?;
@@ -0,0 +1,67 @@
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewPackageEvent;
+use crate::db::models::PackageEvent;
+use crate::db::models::Repo;
+use crate::db::Paginated;
+
+use super::schema;
+
+#[throws]
+pub fn create_package_event(conn: &PgConnection, event: &NewPackageEvent) -> PackageEvent {
+    use schema::package_event::dsl as pe;
+    diesel::insert_into(pe::package_event)
+        .values(event)
+        .get_result(conn)?
+}
+
+/// Returns the latest events of the repository, newest first.
+#[throws]
+pub fn get_package_events_by_repo(conn: &PgConnection, repo_id: i32, limit: i64) -> Vec<PackageEvent> {
+    use schema::package_event::dsl as pe;
+    pe::package_event
+        .filter(pe::repo_id.eq(repo_id))
+        .order_by(pe::id.desc())
+        .limit(limit)
+        .load(conn)?
+}
+
+/// Returns the events of the repository, newest first, optionally only those
+/// of the packages with the given name.
+#[throws]
+pub fn get_package_history(conn: &PgConnection, repo_id: i32, name: Option<&str>, page: usize) -> Paginated<PackageEvent> {
+    use schema::package_event::dsl as pe;
+    let query = || {
+        let mut query = pe::package_event
+            .filter(pe::repo_id.eq(repo_id))
+            .into_boxed::<Pg>();
+        if let Some(name) = name {
+            query = query.filter(pe::name.eq(name));
+        }
+        query
+    };
+    let total_items = query().count().first::<i64>(conn)? as usize;
+    let limit = 100;
+    let items = query()
+        .order_by(pe::id.desc())
+        .offset((page * limit) as i64)
+        .limit(limit as i64)
+        .load(conn)?;
+    Paginated::new(items, total_items, page, limit)
+}
+
+/// Returns the latest events of all repositories of the account, newest first.
+#[throws]
+pub fn get_package_events_by_account(conn: &PgConnection, account_id: i32, limit: i64) -> Vec<(PackageEvent, Repo)> {
+    use schema::package_event::dsl as pe;
+    use schema::repo::dsl as r;
+    pe::package_event
+        .inner_join(r::repo)
+        .filter(r::owner_id.eq(account_id))
+        .order_by(pe::id.desc())
+        .limit(limit)
+        .load(conn)?
+}
@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewPeriodicTask;
+use crate::db::models::PeriodicTask;
+
+use super::schema;
+
+/// Adds the task if it does not exist yet, in which case it is due right away.
+/// If the period of an existing task changed, its next run is moved to
+/// `next_run`, so that the new period applies from now on.
+#[throws]
+pub fn register_periodic_task(conn: &PgConnection, name: &str, period: i32, next_run: NaiveDateTime) {
+    use schema::periodic_task::dsl as pt;
+    let task = NewPeriodicTask { name: name.to_owned(), period };
+    diesel::insert_into(pt::periodic_task)
+        .values(&task)
+        .on_conflict(pt::name)
+        .do_nothing()
+        .execute(conn)?;
+    diesel::update(pt::periodic_task)
+        .filter(pt::name.eq(name))
+        .filter(pt::period.ne(period))
+        .set((
+            pt::period.eq(period),
+            pt::next_run.eq(next_run)))
+        .execute(conn)?;
+}
+
+/// Marks the task as run if it is due. Returns whether the task was due, which
+/// is true for exactly one caller, even if several workers race for it.
+#[throws]
+pub fn claim_periodic_task(conn: &PgConnection, name: &str, now: NaiveDateTime, next_run: NaiveDateTime) -> bool {
+    use schema::periodic_task::dsl as pt;
+    let updated = diesel::update(pt::periodic_task)
+        .filter(pt::name.eq(name))
+        .filter(pt::next_run.le(now))
+        .set((
+            pt::last_run.eq(now),
+            pt::next_run.eq(next_run)))
+        .execute(conn)?;
+    updated > 0
+}
+
+#[throws]
+pub fn get_periodic_tasks(conn: &PgConnection) -> Vec<PeriodicTask> {
+    use schema::periodic_task::dsl as pt;
+    pt::periodic_task
+        .order_by(pt::name.asc())
+        .load(conn)?
+}
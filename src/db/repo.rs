@@ -3,9 +3,12 @@ use diesel::prelude::*;
 use diesel::result::Error;
 use fehler::throws;
 
+use crate::db::models::DependencyKind;
 use crate::db::models::NewRepo;
+use crate::db::models::Package;
 use crate::db::models::Repo;
 
+use super::remove_package;
 use super::schema;
 
 #[throws]
@@ -48,8 +51,28 @@ pub fn create_repo(conn: &PgConnection, repo: &NewRepo) -> Repo {
         .get_result(conn)?
 }
 
+/// Removes the repository together with its packages, and returns the
+/// packages, so that their files can be removed.
 #[throws]
-pub fn get_depends_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<String> {
+pub fn delete_repo(conn: &PgConnection, id: i32) -> Vec<Package> {
+    use schema::package::dsl as p;
+    use schema::repo::dsl as r;
+    conn.transaction::<_, Error, _>(|| {
+        let packages = p::package
+            .filter(p::repo_id.eq(id))
+            .load::<Package>(conn)?;
+        for package in &packages {
+            remove_package(conn, package.id)?;
+        }
+        diesel::delete(r::repo)
+            .filter(r::id.eq(id))
+            .execute(conn)?;
+        Ok(packages)
+    })?
+}
+
+#[throws]
+pub fn get_depends_by_repo(conn: &PgConnection, repo_id: i32, kind: DependencyKind) -> Vec<(i32, String, String)> {
     use schema::repo::dsl as r;
     use schema::package::dsl as p;
     use schema::package_depends::dsl as pd;
@@ -57,13 +80,14 @@ pub fn get_depends_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<String> {
         .inner_join(p::package.inner_join(pd::package_depends))
         .filter(r::id.eq(repo_id))
         .filter(p::active.eq(true))
-        .select(pd::depends)
+        .filter(pd::kind.eq(kind))
+        .select((p::id, p::arch, pd::depends))
         .distinct()
         .load(conn)?
 }
 
 #[throws]
-pub fn get_provides_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<(String, String)> {
+pub fn get_provides_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<(i32, String, String)> {
     use schema::repo::dsl as r;
     use schema::package::dsl as p;
     use schema::package_provides::dsl as pp;
@@ -71,7 +95,22 @@ pub fn get_provides_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<(String, S
         .inner_join(p::package.inner_join(pp::package_provides))
         .filter(r::id.eq(repo_id))
         .filter(p::active.eq(true))
-        .select((pp::provides, p::version))
+        .select((p::id, p::arch, pp::provides))
+        .distinct()
+        .load(conn)?
+}
+
+/// Returns the architectures of the active packages in the repository, not
+/// counting `any`.
+#[throws]
+pub fn get_archs_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<String> {
+    use schema::package::dsl as p;
+    p::package
+        .filter(p::repo_id.eq(repo_id))
+        .filter(p::active.eq(true))
+        .filter(p::arch.ne("any"))
+        .select(p::arch)
         .distinct()
+        .order_by(p::arch.asc())
         .load(conn)?
 }
@@ -0,0 +1,56 @@
+use diesel::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewRepoLayer;
+use crate::db::models::Repo;
+use crate::db::models::RepoLayer;
+
+use super::schema;
+
+#[throws]
+pub fn create_repo_layer(conn: &PgConnection, repo_id: i32, base_repo_id: i32) -> RepoLayer {
+    use schema::repo_layer::dsl as rl;
+    let repo_layer = NewRepoLayer { repo_id, base_repo_id };
+    diesel::insert_into(rl::repo_layer)
+        .values(repo_layer)
+        .get_result(conn)?
+}
+
+#[throws]
+pub fn delete_repo_layer(conn: &PgConnection, repo_id: i32, base_repo_id: i32) {
+    use schema::repo_layer::dsl as rl;
+    diesel::delete(rl::repo_layer)
+        .filter(rl::repo_id.eq(repo_id))
+        .filter(rl::base_repo_id.eq(base_repo_id))
+        .execute(conn)?;
+}
+
+/// Returns the repositories that the repository layers on.
+#[throws]
+pub fn get_base_repos(conn: &PgConnection, repo_id: i32) -> Vec<Repo> {
+    use schema::repo::dsl as r;
+    use schema::repo_layer::dsl as rl;
+    let base_repo_ids = rl::repo_layer
+        .filter(rl::repo_id.eq(repo_id))
+        .select(rl::base_repo_id);
+    r::repo
+        .filter(r::id.eq_any(base_repo_ids))
+        .order_by(r::id.asc())
+        .load(conn)?
+}
+
+/// Returns the repositories that layer on the repository.
+#[throws]
+pub fn get_layered_repos(conn: &PgConnection, base_repo_id: i32) -> Vec<Repo> {
+    use schema::repo::dsl as r;
+    use schema::repo_layer::dsl as rl;
+    let repo_ids = rl::repo_layer
+        .filter(rl::base_repo_id.eq(base_repo_id))
+        .select(rl::repo_id);
+    r::repo
+        .filter(r::id.eq_any(repo_ids))
+        .order_by(r::id.asc())
+        .load(conn)?
+}
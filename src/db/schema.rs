@@ -12,6 +12,21 @@ table! {
         id -> Int4,
         package -> Varchar,
         version -> Varchar,
+        maintainer -> Nullable<Varchar>,
+        out_of_date -> Nullable<Timestamp>,
+        last_modified -> Nullable<Timestamp>,
+        last_checked -> Timestamp,
+    }
+}
+
+table! {
+    install_problem (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        package_id -> Int4,
+        arch -> Varchar,
+        kind -> Varchar,
+        message -> Text,
     }
 }
 
@@ -21,6 +36,14 @@ table! {
         tag -> Varchar,
         spec -> Jsonb,
         worker -> Nullable<Varchar>,
+        status -> Varchar,
+        error -> Nullable<Text>,
+        created -> Timestamp,
+        updated -> Timestamp,
+        repo_id -> Nullable<Int4>,
+        package_id -> Nullable<Int4>,
+        run_after -> Nullable<Timestamp>,
+        lease_until -> Nullable<Timestamp>,
     }
 }
 
@@ -29,6 +52,44 @@ table! {
         id -> Int4,
         repo_id -> Int4,
         dependency -> Varchar,
+        arch -> Varchar,
+        package_id -> Int4,
+        kind -> Varchar,
+    }
+}
+
+table! {
+    needed_rebuild (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        package_id -> Int4,
+        arch -> Varchar,
+        dependency -> Varchar,
+        available -> Varchar,
+    }
+}
+
+table! {
+    notification (id) {
+        id -> Int4,
+        account_id -> Int4,
+        kind -> Varchar,
+        message -> Text,
+        created -> Timestamp,
+        sent -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    notification_preference (account_id) {
+        account_id -> Int4,
+        email -> Varchar,
+        outdated -> Bool,
+        missing_deps -> Bool,
+        failed_jobs -> Bool,
+        confirmed -> Bool,
+        confirmation_token -> Nullable<Varchar>,
+        confirmation_sent -> Nullable<Timestamp>,
     }
 }
 
@@ -46,6 +107,7 @@ table! {
         active -> Bool,
         deleted -> Bool,
         repo_id -> Int4,
+        description -> Nullable<Varchar>,
     }
 }
 
@@ -54,6 +116,20 @@ table! {
         id -> Int4,
         package_id -> Int4,
         depends -> Varchar,
+        kind -> Varchar,
+    }
+}
+
+table! {
+    package_event (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        kind -> Varchar,
+        name -> Varchar,
+        version -> Varchar,
+        arch -> Varchar,
+        description -> Nullable<Varchar>,
+        created -> Timestamp,
     }
 }
 
@@ -65,6 +141,16 @@ table! {
     }
 }
 
+table! {
+    periodic_task (id) {
+        id -> Int4,
+        name -> Varchar,
+        period -> Int4,
+        last_run -> Nullable<Timestamp>,
+        next_run -> Timestamp,
+    }
+}
+
 table! {
     repo (id) {
         id -> Int4,
@@ -82,6 +168,25 @@ table! {
     }
 }
 
+table! {
+    repo_layer (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        base_repo_id -> Int4,
+    }
+}
+
+table! {
+    upstream_collision (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        package_id -> Int4,
+        arch -> Varchar,
+        upstream_repo -> Varchar,
+        upstream_version -> Varchar,
+    }
+}
+
 table! {
     token (id) {
         id -> Int4,
@@ -91,23 +196,88 @@ table! {
     }
 }
 
+table! {
+    version_source (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        package -> Varchar,
+        kind -> Varchar,
+        source -> Varchar,
+        pattern -> Nullable<Varchar>,
+        version -> Nullable<Varchar>,
+        error -> Nullable<Varchar>,
+        last_checked -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    webhook (id) {
+        id -> Int4,
+        repo_id -> Int4,
+        url -> Varchar,
+        secret -> Varchar,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    webhook_delivery (id) {
+        id -> Int4,
+        webhook_id -> Int4,
+        event -> Varchar,
+        payload -> Jsonb,
+        attempts -> Int4,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        created -> Timestamp,
+        last_attempt -> Nullable<Timestamp>,
+        delivered -> Nullable<Timestamp>,
+    }
+}
+
+joinable!(install_problem -> package (package_id));
+joinable!(install_problem -> repo (repo_id));
+joinable!(job -> package (package_id));
+joinable!(job -> repo (repo_id));
+joinable!(missing_dep -> package (package_id));
 joinable!(missing_dep -> repo (repo_id));
+joinable!(needed_rebuild -> package (package_id));
+joinable!(needed_rebuild -> repo (repo_id));
+joinable!(notification -> account (account_id));
+joinable!(notification_preference -> account (account_id));
 joinable!(package -> repo (repo_id));
 joinable!(package_depends -> package (package_id));
+joinable!(package_event -> repo (repo_id));
 joinable!(package_provides -> package (package_id));
 joinable!(repo -> account (owner_id));
 joinable!(repo_action -> package (package_id));
 joinable!(token -> account (account_id));
+joinable!(upstream_collision -> package (package_id));
+joinable!(upstream_collision -> repo (repo_id));
+joinable!(version_source -> repo (repo_id));
+joinable!(webhook -> repo (repo_id));
+joinable!(webhook_delivery -> webhook (webhook_id));
 
 allow_tables_to_appear_in_same_query!(
     account,
     aur_version,
+    install_problem,
     job,
     missing_dep,
+    needed_rebuild,
+    notification,
+    notification_preference,
     package,
     package_depends,
+    package_event,
     package_provides,
+    periodic_task,
     repo,
     repo_action,
+    repo_layer,
     token,
+    upstream_collision,
+    version_source,
+    webhook,
+    webhook_delivery,
 );
@@ -0,0 +1,28 @@
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewUpstreamCollision;
+use crate::db::models::UpstreamCollision;
+
+use super::schema;
+
+#[throws]
+pub fn replace_upstream_collisions(conn: &PgConnection, repo_id: i32, collisions: Vec<NewUpstreamCollision>) {
+    use schema::upstream_collision::dsl as uc;
+    diesel::delete(uc::upstream_collision)
+        .filter(uc::repo_id.eq(repo_id))
+        .execute(conn)?;
+    diesel::insert_into(uc::upstream_collision)
+        .values(collisions)
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn get_upstream_collisions_by_package(conn: &PgConnection, package_id: i32) -> Vec<UpstreamCollision> {
+    use schema::upstream_collision::dsl as uc;
+    uc::upstream_collision
+        .filter(uc::package_id.eq(package_id))
+        .order_by((uc::arch.asc(), uc::upstream_repo.asc()))
+        .load(conn)?
+}
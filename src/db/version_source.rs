@@ -0,0 +1,72 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewVersionSource;
+use crate::db::models::VersionSource;
+
+use super::schema;
+
+/// Sets the version source of the package, replacing the previous one and its
+/// results.
+#[throws]
+pub fn set_version_source(conn: &PgConnection, version_source: &NewVersionSource) {
+    use schema::version_source::dsl as vs;
+    diesel::insert_into(vs::version_source)
+        .values(version_source)
+        .on_conflict((vs::repo_id, vs::package))
+        .do_update()
+        .set((
+            vs::kind.eq(&version_source.kind),
+            vs::source.eq(&version_source.source),
+            vs::pattern.eq(&version_source.pattern),
+            vs::version.eq(None::<String>),
+            vs::error.eq(None::<String>),
+            vs::last_checked.eq(None::<NaiveDateTime>)
+        ))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn get_version_source(conn: &PgConnection, repo_id: i32, package: &str) -> Option<VersionSource> {
+    use schema::version_source::dsl as vs;
+    vs::version_source
+        .filter(vs::repo_id.eq(repo_id))
+        .filter(vs::package.eq(package))
+        .first(conn)
+        .optional()?
+}
+
+#[throws]
+pub fn get_version_sources(conn: &PgConnection) -> Vec<VersionSource> {
+    use schema::version_source::dsl as vs;
+    vs::version_source
+        .order_by(vs::id.asc())
+        .load(conn)?
+}
+
+#[throws]
+pub fn delete_version_source(conn: &PgConnection, repo_id: i32, package: &str) {
+    use schema::version_source::dsl as vs;
+    diesel::delete(vs::version_source)
+        .filter(vs::repo_id.eq(repo_id))
+        .filter(vs::package.eq(package))
+        .execute(conn)?;
+}
+
+/// Records the result of checking the version source.
+#[throws]
+pub fn set_version_source_result(
+    conn: &PgConnection,
+    id: i32,
+    version: Option<String>,
+    error: Option<String>,
+    checked: NaiveDateTime
+) {
+    use schema::version_source::dsl as vs;
+    diesel::update(vs::version_source)
+        .filter(vs::id.eq(id))
+        .set((vs::version.eq(version), vs::error.eq(error), vs::last_checked.eq(checked)))
+        .execute(conn)?;
+}
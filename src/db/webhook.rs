@@ -0,0 +1,104 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use fehler::throws;
+
+use crate::db::models::NewWebhook;
+use crate::db::models::NewWebhookDelivery;
+use crate::db::models::Webhook;
+use crate::db::models::WebhookDelivery;
+
+use super::schema;
+
+#[throws]
+pub fn create_webhook(conn: &PgConnection, webhook: &NewWebhook) -> Webhook {
+    use schema::webhook::dsl as w;
+    diesel::insert_into(w::webhook)
+        .values(webhook)
+        .get_result(conn)?
+}
+
+#[throws]
+pub fn get_webhooks_by_repo(conn: &PgConnection, repo_id: i32) -> Vec<Webhook> {
+    use schema::webhook::dsl as w;
+    w::webhook
+        .filter(w::repo_id.eq(repo_id))
+        .order_by(w::id.asc())
+        .load(conn)?
+}
+
+/// Removes the webhook together with its deliveries.
+#[throws]
+pub fn delete_webhook(conn: &PgConnection, repo_id: i32, id: i32) {
+    use schema::webhook::dsl as w;
+    diesel::delete(w::webhook)
+        .filter(w::repo_id.eq(repo_id))
+        .filter(w::id.eq(id))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn create_webhook_delivery(conn: &PgConnection, delivery: &NewWebhookDelivery) -> WebhookDelivery {
+    use schema::webhook_delivery::dsl as wd;
+    diesel::insert_into(wd::webhook_delivery)
+        .values(delivery)
+        .get_result(conn)?
+}
+
+/// Returns the delivery and its webhook, unless the webhook has been removed
+/// in the meantime.
+#[throws]
+pub fn get_webhook_delivery(conn: &PgConnection, id: i32) -> Option<(WebhookDelivery, Webhook)> {
+    use schema::webhook::dsl as w;
+    use schema::webhook_delivery::dsl as wd;
+    wd::webhook_delivery
+        .inner_join(w::webhook)
+        .filter(wd::id.eq(id))
+        .first(conn)
+        .optional()?
+}
+
+/// Returns the latest deliveries of the webhooks of the repository, newest
+/// first.
+#[throws]
+pub fn get_webhook_deliveries_by_repo(conn: &PgConnection, repo_id: i32, limit: i64) -> Vec<(WebhookDelivery, Webhook)> {
+    use schema::webhook::dsl as w;
+    use schema::webhook_delivery::dsl as wd;
+    wd::webhook_delivery
+        .inner_join(w::webhook)
+        .filter(w::repo_id.eq(repo_id))
+        .order_by(wd::id.desc())
+        .limit(limit)
+        .load(conn)?
+}
+
+/// Records an attempt to deliver the payload.
+#[throws]
+pub fn set_webhook_delivery_attempt(
+    conn: &PgConnection,
+    id: i32,
+    status_code: Option<i32>,
+    error: Option<&str>,
+    attempted: NaiveDateTime
+) {
+    use schema::webhook_delivery::dsl as wd;
+    let delivered = if error.is_none() { Some(attempted) } else { None };
+    diesel::update(wd::webhook_delivery)
+        .filter(wd::id.eq(id))
+        .set((
+            wd::attempts.eq(wd::attempts + 1),
+            wd::status_code.eq(status_code),
+            wd::error.eq(error),
+            wd::last_attempt.eq(attempted),
+            wd::delivered.eq(delivered)
+        ))
+        .execute(conn)?;
+}
+
+#[throws]
+pub fn delete_webhook_deliveries(conn: &PgConnection, created_before: NaiveDateTime) -> usize {
+    use schema::webhook_delivery::dsl as wd;
+    diesel::delete(wd::webhook_delivery)
+        .filter(wd::created.lt(created_before))
+        .execute(conn)?
+}
@@ -10,6 +10,9 @@ pub enum Error {
     #[error("Database error")]
     Diesel(#[from] diesel::result::Error),
 
+    #[error("Database connection error")]
+    Connection(#[from] diesel::ConnectionError),
+
     #[error("Serde error")]
     Serde(#[from] serde_json::error::Error),
 
@@ -19,6 +22,7 @@ pub enum Error {
     #[error("Parse error")]
     ParseInt(#[from] std::num::ParseIntError),
 
+    #[cfg(feature = "alpm")]
     #[error("Alpm error")]
     Alpm(#[from] alpm::Error),
 
@@ -0,0 +1,154 @@
+use chrono::NaiveDateTime;
+
+use crate::db::models::PackageEvent;
+use crate::db::models::PackageEventKind;
+
+/// An Atom feed (RFC 4287), rendered by `to_xml`.
+#[derive(Debug)]
+pub struct Feed {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub entries: Vec<Entry>
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub updated: NaiveDateTime,
+    pub summary: Option<String>
+}
+
+impl Entry {
+    /// Describes the upload or removal of a package, linking to the page of
+    /// its repository.
+    pub fn package_event(repo_url: &str, repo: &str, event: &PackageEvent) -> Entry {
+        let action = match event.kind {
+            PackageEventKind::Uploaded => "uploaded to",
+            PackageEventKind::Removed => "removed from"
+        };
+        Entry {
+            id: format!("{}#event-{}", repo_url, event.id),
+            title: format!("{} {} ({}) {} {}", event.name, event.version, event.arch, action, repo),
+            link: repo_url.to_owned(),
+            updated: event.created,
+            summary: event.description.clone()
+        }
+    }
+}
+
+impl Feed {
+    /// The feed was last updated by its newest entry. An empty feed uses the
+    /// epoch, since Atom requires a date.
+    fn updated(&self) -> NaiveDateTime {
+        self.entries.iter()
+            .map(|e| e.updated)
+            .max()
+            .unwrap_or_else(|| NaiveDateTime::from_timestamp(0, 0))
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        xml += "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n";
+        xml += "<feed xmlns=\"http://www.w3.org/2005/Atom\">\n";
+        xml += &format!("  <id>{}</id>\n", escape(&self.id));
+        xml += &format!("  <title>{}</title>\n", escape(&self.title));
+        xml += &format!("  <link href=\"{}\"/>\n", escape(&self.link));
+        xml += &format!("  <updated>{}</updated>\n", format_date(self.updated()));
+        xml += "  <author><name>paketbote</name></author>\n";
+        for entry in &self.entries {
+            xml += "  <entry>\n";
+            xml += &format!("    <id>{}</id>\n", escape(&entry.id));
+            xml += &format!("    <title>{}</title>\n", escape(&entry.title));
+            xml += &format!("    <link href=\"{}\"/>\n", escape(&entry.link));
+            xml += &format!("    <updated>{}</updated>\n", format_date(entry.updated));
+            if let Some(summary) = &entry.summary {
+                xml += &format!("    <summary>{}</summary>\n", escape(summary));
+            }
+            xml += "  </entry>\n";
+        }
+        xml += "</feed>\n";
+        xml
+    }
+}
+
+fn format_date(date: NaiveDateTime) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Escapes the text for use in content and attributes. Characters that XML
+/// 1.0 does not allow at all, like most control characters, are replaced with
+/// U+FFFD, since readers reject documents that contain them.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\'' => escaped += "&apos;",
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => escaped.push('\u{fffd}'),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    assert_eq!(escape("it's"), "it&apos;s");
+    assert_eq!(escape("a\x01b\x1f\u{ffff}"), "a\u{fffd}b\u{fffd}\u{fffd}");
+    assert_eq!(escape("line\n\tindented\r\n"), "line\n\tindented\r\n");
+}
+
+#[test]
+fn test_to_xml() {
+    let date = |secs| NaiveDateTime::from_timestamp(secs, 0);
+    let event = |id, kind, created, description: Option<&str>| PackageEvent {
+        id,
+        repo_id: 1,
+        kind,
+        name: "foo".to_owned(),
+        version: "1.0-1".to_owned(),
+        arch: "x86_64".to_owned(),
+        description: description.map(|d| d.to_owned()),
+        created: date(created)
+    };
+    let repo_url = "https://example.com/alice/stuff";
+    let feed = Feed {
+        id: repo_url.to_owned(),
+        title: "alice/stuff".to_owned(),
+        link: repo_url.to_owned(),
+        entries: vec![
+            Entry::package_event(repo_url, "stuff", &event(2, PackageEventKind::Removed, 90000, None)),
+            Entry::package_event(repo_url, "stuff", &event(1, PackageEventKind::Uploaded, 3600, Some("Foo & bar"))),
+        ]
+    };
+    assert_eq!(feed.to_xml(), "\
+        <?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        <feed xmlns=\"http://www.w3.org/2005/Atom\">\n  \
+          <id>https://example.com/alice/stuff</id>\n  \
+          <title>alice/stuff</title>\n  \
+          <link href=\"https://example.com/alice/stuff\"/>\n  \
+          <updated>1970-01-02T01:00:00Z</updated>\n  \
+          <author><name>paketbote</name></author>\n  \
+          <entry>\n    \
+            <id>https://example.com/alice/stuff#event-2</id>\n    \
+            <title>foo 1.0-1 (x86_64) removed from stuff</title>\n    \
+            <link href=\"https://example.com/alice/stuff\"/>\n    \
+            <updated>1970-01-02T01:00:00Z</updated>\n  \
+          </entry>\n  \
+          <entry>\n    \
+            <id>https://example.com/alice/stuff#event-1</id>\n    \
+            <title>foo 1.0-1 (x86_64) uploaded to stuff</title>\n    \
+            <link href=\"https://example.com/alice/stuff\"/>\n    \
+            <updated>1970-01-01T01:00:00Z</updated>\n    \
+            <summary>Foo &amp; bar</summary>\n  \
+          </entry>\n\
+        </feed>\n");
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use diesel::PgConnection;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::arch::arch_matches;
+use crate::arch::repo_archs;
+use crate::db::get_active_packages_by_repo;
+use crate::db::get_depends_by_repo;
+use crate::db::get_provides_by_repo;
+use crate::db::models::DependencyKind;
+use crate::error::Error;
+use crate::missing::provider_repos;
+use crate::spec::Spec;
+use crate::syncdb::UpstreamDbs;
+
+/// The dependency graph of the active packages of a repository. Edges point
+/// from a package to the packages that satisfy one of its dependencies.
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// The packages of the repository grouped into stages, such that every
+    /// package only depends on packages of earlier stages. Packages that are
+    /// part of a dependency cycle, or depend on one, end up together in the
+    /// last stage.
+    pub build_order: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub arch: String,
+    pub kind: NodeKind,
+    /// Whether no other package of the repository depends on this package.
+    pub leaf: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    /// A package of the repository.
+    Package,
+    /// A package of a repository the repository layers on.
+    Layer,
+    /// A package of an upstream repository.
+    Upstream,
+    /// A dependency that nothing satisfies.
+    Missing,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub dependency: String,
+}
+
+struct Provided {
+    node: String,
+    arch: String,
+    spec: Spec,
+}
+
+/// The id of the node of a package in the graph.
+pub fn package_node(package_id: i32) -> String {
+    format!("package:{}", package_id)
+}
+
+/// Builds the dependency graph of the repository. Dependencies are resolved
+/// against the repository and the repositories it layers on. If `upstream` is
+/// given, the remaining dependencies are resolved against the upstream
+/// repositories, and those that cannot be resolved become missing nodes.
+#[throws]
+pub fn dependency_graph(conn: &PgConnection, repo_id: i32, upstream: Option<&UpstreamDbs>) -> Graph {
+    let mut nodes = Vec::new();
+    let mut provided = Vec::new();
+    for provider_id in provider_repos(conn, repo_id)? {
+        let kind = if provider_id == repo_id { NodeKind::Package } else { NodeKind::Layer };
+        for package in get_active_packages_by_repo(conn, provider_id)? {
+            nodes.push(Node {
+                id: package_node(package.id),
+                name: package.name,
+                version: Some(package.version),
+                arch: package.arch,
+                kind,
+                leaf: false
+            });
+        }
+        for (package_id, arch, pp) in get_provides_by_repo(conn, provider_id)? {
+            let spec: Spec = pp.parse()?;
+            provided.push(Provided { node: package_node(package_id), arch, spec });
+        }
+    }
+
+    let mut edges = Vec::new();
+    let mut unresolved = Vec::new();
+    for (package_id, arch, pd) in get_depends_by_repo(conn, repo_id, DependencyKind::Depend)? {
+        let from = package_node(package_id);
+        let spec: Spec = pd.parse()?;
+        let satisfiers = provided.iter()
+            .filter(|p| p.node != from && arch_matches(&p.arch, &arch) && p.spec.satisfies(&spec))
+            .map(|p| p.node.clone())
+            .collect::<Vec<_>>();
+        if satisfiers.is_empty() {
+            unresolved.push((from, arch, pd));
+        }
+        for to in satisfiers {
+            let edge = Edge { from: from.clone(), to, dependency: pd.clone() };
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+    }
+
+    if let Some(upstream) = upstream {
+        let archs = repo_archs(conn, repo_id)?;
+        let mut known = HashSet::new();
+        for arch in &archs {
+            let dbs = upstream.get(arch)?;
+            for (from, dependent_arch, dependency) in &unresolved {
+                if !arch_matches(arch, dependent_arch) {
+                    continue;
+                }
+                let (to, node) = match dbs.find_satisfier(dependency) {
+                    Some((_, pkg)) => {
+                        let id = format!("upstream:{}", pkg.name);
+                        let node = Node {
+                            id: id.clone(),
+                            name: pkg.name.clone(),
+                            version: Some(pkg.version.clone()),
+                            arch: arch.clone(),
+                            kind: NodeKind::Upstream,
+                            leaf: false
+                        };
+                        (id, node)
+                    }
+                    None => {
+                        let id = format!("missing:{}", dependency);
+                        let node = Node {
+                            id: id.clone(),
+                            name: dependency.clone(),
+                            version: None,
+                            arch: arch.clone(),
+                            kind: NodeKind::Missing,
+                            leaf: false
+                        };
+                        (id, node)
+                    }
+                };
+                if known.insert(to.clone()) {
+                    nodes.push(node);
+                }
+                let edge = Edge { from: from.clone(), to, dependency: dependency.clone() };
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+    }
+
+    let required = edges.iter()
+        .filter(|e| e.from != e.to)
+        .map(|e| e.to.clone())
+        .collect::<HashSet<_>>();
+    // Only keep the packages of other repositories that something depends on.
+    nodes.retain(|n| n.kind != NodeKind::Layer || required.contains(&n.id));
+    for node in &mut nodes {
+        node.leaf = node.kind == NodeKind::Package && !required.contains(&node.id);
+    }
+
+    let packages = nodes.iter()
+        .filter(|n| n.kind == NodeKind::Package)
+        .map(|n| n.id.clone())
+        .collect::<Vec<_>>();
+    let build_order = build_stages(&packages, &edges);
+    Graph { nodes, edges, build_order }
+}
+
+/// Groups the nodes into stages, such that every node only depends on nodes of
+/// earlier stages. Dependencies on nodes outside of `nodes` are ignored, and
+/// nodes that are part of a cycle, or depend on one, are put together into a
+/// last stage.
+pub fn build_stages(nodes: &[String], edges: &[Edge]) -> Vec<Vec<String>> {
+    let mut pending: HashMap<&str, HashSet<&str>> = nodes.iter()
+        .map(|n| (n.as_str(), HashSet::new()))
+        .collect();
+    for edge in edges {
+        if edge.from != edge.to && pending.contains_key(edge.to.as_str()) {
+            if let Some(depends) = pending.get_mut(edge.from.as_str()) {
+                depends.insert(edge.to.as_str());
+            }
+        }
+    }
+
+    let mut stages = Vec::new();
+    while !pending.is_empty() {
+        let mut stage = pending.iter()
+            .filter(|(_, depends)| depends.is_empty())
+            .map(|(node, _)| node.to_string())
+            .collect::<Vec<_>>();
+        if stage.is_empty() {
+            stage = pending.keys().map(|n| n.to_string()).collect();
+        }
+        stage.sort();
+        for node in &stage {
+            pending.remove(node.as_str());
+        }
+        for depends in pending.values_mut() {
+            for node in &stage {
+                depends.remove(node.as_str());
+            }
+        }
+        stages.push(stage);
+    }
+    stages
+}
+
+impl Graph {
+    /// Renders the graph in the Graphviz DOT language.
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph {} {{", quote(name)).unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"sans-serif\"];").unwrap();
+        for node in &self.nodes {
+            let label = match &node.version {
+                Some(version) => format!("{}\n{} ({})", node.name, version, node.arch),
+                None => node.name.clone()
+            };
+            let style = match node.kind {
+                NodeKind::Package if node.leaf => "style=\"filled,bold\", fillcolor=\"#d4edda\"",
+                NodeKind::Package => "style=filled, fillcolor=\"#d4edda\"",
+                NodeKind::Layer => "style=filled, fillcolor=\"#d1ecf1\"",
+                NodeKind::Upstream => "style=dashed",
+                NodeKind::Missing => "style=filled, fillcolor=\"#f8d7da\", color=\"#dc3545\""
+            };
+            writeln!(dot, "    {} [label={}, {}];", quote(&node.id), quote(&label), style).unwrap();
+        }
+        for edge in &self.edges {
+            writeln!(dot, "    {} -> {} [tooltip={}];",
+                quote(&edge.from), quote(&edge.to), quote(&edge.dependency)).unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+fn quote(id: &str) -> String {
+    let id = id.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", id)
+}
+
+#[test]
+fn test_build_stages() {
+    let nodes = ["a", "b", "c", "d", "e", "f"].iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>();
+    let edge = |from: &str, to: &str| Edge {
+        from: from.to_owned(),
+        to: to.to_owned(),
+        dependency: to.to_owned()
+    };
+    let edges = vec![
+        edge("a", "b"), edge("a", "upstream:glibc"), edge("b", "c"), edge("d", "c"),
+        edge("c", "c"), edge("e", "f"), edge("f", "e"),
+    ];
+    assert_eq!(build_stages(&nodes, &edges), vec![
+        vec!["c".to_owned()],
+        vec!["b".to_owned(), "d".to_owned()],
+        vec!["a".to_owned()],
+        vec!["e".to_owned(), "f".to_owned()],
+    ]);
+    assert!(build_stages(&[], &edges).is_empty());
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!(quote("a \"b\"\nc\\"), "\"a \\\"b\\\"\\nc\\\\\"");
+}
@@ -0,0 +1,134 @@
+use ::alpm::Alpm;
+use ::alpm::PrepareResult;
+use ::alpm::TransFlag;
+use diesel::PgConnection;
+use fehler::throws;
+use tempfile::TempDir;
+
+use crate::alpm;
+use crate::arch::arch_matches;
+use crate::arch::repo_archs;
+use crate::config::Upstream;
+use crate::consistency::repo_db_path;
+use crate::db::get_active_packages_by_repo;
+use crate::db::models::Package;
+use crate::error::Error;
+use crate::missing::provider_repos;
+
+/// A reason why a package cannot be installed with `pacman -S` on a system
+/// that uses the repository (and the repositories it layers on) in addition
+/// to the upstream repositories.
+#[derive(Debug)]
+pub struct InstallProblem {
+    pub package_id: i32,
+    pub arch: String,
+    /// One of `unsatisfied`, `conflict`, `arch` or `error`.
+    pub kind: String,
+    pub message: String,
+}
+
+/// Simulates installing every active package of the repository on an empty
+/// system, once for every architecture the repository serves. This resolves
+/// the full dependency closure, so it also finds dependencies of dependencies
+/// that cannot be satisfied, unsatisfiable version constraints and conflicts.
+#[throws]
+pub fn check_installability(conn: &PgConnection, repo_id: i32, upstream: &Upstream) -> Vec<InstallProblem> {
+    let own_db = db_name(repo_id);
+    let mut extra = Vec::new();
+    for provider_id in provider_repos(conn, repo_id)? {
+        let db = repo_db_path(provider_id);
+        if db.exists() {
+            extra.push((db_name(provider_id), db));
+        }
+    }
+    if !extra.iter().any(|(name, _)| *name == own_db) {
+        // Nothing has been published yet.
+        return Vec::new();
+    }
+
+    let packages = get_active_packages_by_repo(conn, repo_id)?;
+    let archs = repo_archs(conn, repo_id)?;
+
+    let mut problems = Vec::new();
+    for arch in &archs {
+        // Every run gets a directory of its own, which is removed when it is
+        // dropped, after the handle that uses it.
+        let dir = simulation_dir(repo_id, arch)?;
+        let alpm = alpm::create_simulation(dir.path(), arch, upstream.repos(arch), &extra)?;
+        problems.extend(check_arch(&alpm, &own_db, arch, &packages)?);
+    }
+    problems
+}
+
+#[throws]
+fn simulation_dir(repo_id: i32, arch: &str) -> TempDir {
+    tempfile::Builder::new()
+        .prefix(&format!("paketbote-installcheck-{}-{}-", repo_id, arch))
+        .tempdir()?
+}
+
+fn db_name(repo_id: i32) -> String {
+    format!("paketbote-{}", repo_id)
+}
+
+#[throws]
+fn check_arch(alpm: &Alpm, own_db: &str, arch: &str, packages: &[Package]) -> Vec<InstallProblem> {
+    let db = alpm.syncdbs().into_iter()
+        .find(|db| db.name() == own_db)
+        .ok_or_else(|| format!("Database {} is not registered", own_db))?;
+
+    let mut problems = Vec::new();
+    for package in packages.iter().filter(|p| arch_matches(&p.arch, arch)) {
+        // The database may lag behind, in which case the package is checked
+        // once the database has caught up.
+        let pkg = match db.pkg(package.name.as_str()) {
+            Ok(pkg) => pkg,
+            Err(_) => continue
+        };
+        if format!("{}", pkg.version()) != package.version {
+            continue;
+        }
+        problems.extend(simulate(alpm, pkg, package.id, arch)?);
+    }
+    problems
+}
+
+#[throws]
+fn simulate(alpm: &Alpm, pkg: ::alpm::Package, package_id: i32, arch: &str) -> Vec<InstallProblem> {
+    alpm.trans_init(TransFlag::NO_LOCK)?;
+    let result = match alpm.trans_add_pkg(pkg) {
+        Ok(()) => Ok(prepare(alpm, package_id, arch)),
+        Err(error) => Err(error)
+    };
+    alpm.trans_release()?;
+    result?
+}
+
+fn prepare(alpm: &Alpm, package_id: i32, arch: &str) -> Vec<InstallProblem> {
+    let problem = |kind: &str, message: String| InstallProblem {
+        package_id,
+        arch: arch.to_owned(),
+        kind: kind.to_owned(),
+        message
+    };
+    match alpm.trans_prepare() {
+        Ok(()) => Vec::new(),
+        Err((PrepareResult::UnsatisfiedDeps(missing), _)) => missing.iter()
+            .map(|m| {
+                let message = match m.causing_pkg() {
+                    Some(causing) => format!("{} requires {} (needed by {})", m.target(), m.depend(), causing),
+                    None => format!("{} requires {}", m.target(), m.depend())
+                };
+                problem("unsatisfied", message)
+            })
+            .collect(),
+        Err((PrepareResult::ConflictingDeps(conflicts), _)) => conflicts.iter()
+            .map(|c| problem("conflict", format!(
+                "{} and {} are in conflict ({})", c.package1(), c.package2(), c.reason())))
+            .collect(),
+        Err((PrepareResult::PkgInvalidArch(pkgs), _)) => pkgs.iter()
+            .map(|p| problem("arch", format!("{} has an invalid architecture", p.name())))
+            .collect(),
+        Err((_, error)) => vec![problem("error", error.to_string())]
+    }
+}
@@ -1,3 +1,7 @@
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
 use diesel::PgConnection;
 use fehler::throws;
 use serde::de::DeserializeOwned;
@@ -6,7 +10,11 @@ use serde::Serialize;
 
 use crate::db;
 use crate::db::create_job;
+use crate::db::get_package;
+use crate::db::models::Job;
+use crate::db::models::JobStatus;
 use crate::error::Error;
+use crate::missing::layered_repos;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct RepoAction {
@@ -14,7 +22,7 @@ pub struct RepoAction {
     pub operation: RepoActionOp,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RepoActionOp {
     Add, Remove,
 }
@@ -29,9 +37,10 @@ impl RepoAction {
 }
 
 #[throws]
-pub fn create_repo_action(conn: &PgConnection, package_id: i32, operation: RepoActionOp) {
+pub fn create_repo_action(conn: &PgConnection, package_id: i32, operation: RepoActionOp) -> Job {
+    let package = get_package(conn, package_id)?;
     let repo_action = RepoAction { package_id, operation };
-    create_job(conn, "repo_action".to_owned(), repo_action)?;
+    create_job(conn, "repo_action".to_owned(), repo_action, Some(package.repo_id), Some(package_id))?
 }
 
 #[throws]
@@ -39,15 +48,42 @@ pub fn get_repo_action(conn: &PgConnection, worker: &str) -> Option<(i32, RepoAc
     claim_job(conn, "repo_action", worker)?
 }
 
+/// Recreates the database of a repository from its active packages.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RepoRebuild {
+    pub repo_id: i32
+}
+
+#[throws]
+pub fn create_repo_rebuild(conn: &PgConnection, repo_id: i32) -> Job {
+    let repo_rebuild = RepoRebuild { repo_id };
+    create_job(conn, "repo_rebuild".to_owned(), repo_rebuild, Some(repo_id), None)?
+}
+
+#[throws]
+pub fn get_repo_rebuild(conn: &PgConnection, worker: &str) -> Option<(i32, RepoRebuild)> {
+    claim_job(conn, "repo_rebuild", worker)?
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct CheckDeps {
     pub repo_id: i32
 }
 
 #[throws]
-pub fn create_check_deps(conn: &PgConnection, repo_id: i32) {
+pub fn create_check_deps(conn: &PgConnection, repo_id: i32) -> Job {
     let check_deps = CheckDeps { repo_id };
-    create_job(conn, "check_deps".to_owned(), check_deps)?;
+    create_job(conn, "check_deps".to_owned(), check_deps, Some(repo_id), None)?
+}
+
+/// Enqueues a dependency check for the repository and for every repository
+/// that layers on it, directly or through other layers, because the packages
+/// of the repository may satisfy their dependencies.
+#[throws]
+pub fn create_check_deps_with_layered(conn: &PgConnection, repo_id: i32) {
+    for repo_id in layered_repos(conn, repo_id)? {
+        create_check_deps(conn, repo_id)?;
+    }
 }
 
 #[throws]
@@ -55,6 +91,24 @@ pub fn get_check_deps(conn: &PgConnection, worker: &str) -> Option<(i32, CheckDe
     claim_job(conn, "check_deps", worker)?
 }
 
+/// Sends a webhook delivery. Failed deliveries are rescheduled with a growing
+/// delay until they succeed or run out of attempts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DeliverWebhook {
+    pub delivery_id: i32
+}
+
+#[throws]
+pub fn create_deliver_webhook(conn: &PgConnection, repo_id: i32, delivery_id: i32) -> Job {
+    let deliver_webhook = DeliverWebhook { delivery_id };
+    create_job(conn, "deliver_webhook".to_owned(), deliver_webhook, Some(repo_id), None)?
+}
+
+#[throws]
+pub fn get_deliver_webhook(conn: &PgConnection, worker: &str) -> Option<(i32, DeliverWebhook)> {
+    claim_job(conn, "deliver_webhook", worker)?
+}
+
 #[throws]
 pub fn claim_job<T: DeserializeOwned>(conn: &PgConnection, tag: &str, worker: &str) -> Option<(i32, T)> {
     if let Some(job) = db::claim_job(conn, tag, worker)? {
@@ -63,3 +117,64 @@ pub fn claim_job<T: DeserializeOwned>(conn: &PgConnection, tag: &str, worker: &s
         None
     }
 }
+
+/// Blocks until the job has either finished or failed, or until the timeout
+/// has elapsed, and returns the last known state of the job. The state is
+/// looked up with `get_job`, so that callers decide whether to keep a
+/// connection while sleeping.
+#[throws(E)]
+pub fn wait_for_job<E>(timeout: Duration, mut get_job: impl FnMut() -> Result<Option<Job>, E>) -> Option<Job> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let job = get_job()?;
+        let done = match &job {
+            Some(job) => job.status == JobStatus::Finished || job.status == JobStatus::Failed,
+            None => true
+        };
+        if done || Instant::now() >= deadline {
+            break job;
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Returns a short, human readable description of what the job does.
+pub fn describe_job(job: &Job) -> String {
+    match job.tag.as_str() {
+        "repo_action" => match serde_json::from_value::<RepoAction>(job.spec.clone()) {
+            Ok(RepoAction { operation: RepoActionOp::Add, .. }) => "activation".to_owned(),
+            Ok(RepoAction { operation: RepoActionOp::Remove, .. }) => "removal".to_owned(),
+            Err(_) => "repo action".to_owned()
+        },
+        "check_deps" => "dependency check".to_owned(),
+        "repo_rebuild" => "rebuild".to_owned(),
+        "deliver_webhook" => "webhook delivery".to_owned(),
+        tag => tag.replace("_", " ")
+    }
+}
+
+#[test]
+fn test_describe_job() {
+    let date = chrono::NaiveDateTime::from_timestamp(0, 0);
+    let job = |tag: &str, spec: serde_json::Value| Job {
+        id: 1,
+        tag: tag.to_owned(),
+        spec,
+        worker: None,
+        status: JobStatus::Pending,
+        error: None,
+        created: date,
+        updated: date,
+        repo_id: None,
+        package_id: None,
+        run_after: None,
+        lease_until: None
+    };
+    let add = serde_json::to_value(RepoAction::new_add(1)).unwrap();
+    let remove = serde_json::to_value(RepoAction::new_remove(1)).unwrap();
+    assert_eq!(describe_job(&job("repo_action", add)), "activation");
+    assert_eq!(describe_job(&job("repo_action", remove)), "removal");
+    assert_eq!(describe_job(&job("repo_action", serde_json::json!({}))), "repo action");
+    assert_eq!(describe_job(&job("check_deps", serde_json::json!({}))), "dependency check");
+    assert_eq!(describe_job(&job("check_aur_versions", serde_json::json!({}))), "check aur versions");
+}
@@ -23,16 +23,37 @@ use rocket::Rocket;
 use crate::db::models::{Compression, Package};
 use crate::error::Error;
 
+#[cfg(feature = "alpm")]
 pub mod alpm;
+pub mod arch;
 pub mod aur;
+pub mod client;
+pub mod collision;
+pub mod config;
+pub mod consistency;
 pub mod db;
 pub mod error;
+pub mod feed;
+pub mod graph;
+#[cfg(feature = "alpm")]
+pub mod installability;
 pub mod jobs;
+pub mod maintenance;
 pub mod missing;
+pub mod net;
+pub mod notification;
 pub mod obsolete;
 pub mod pkginfo;
+pub mod rdeps;
+pub mod rebuild;
+pub mod scheduler;
 pub mod spec;
+pub mod syncdb;
+pub mod vercmp;
+pub mod version_source;
 pub mod web;
+pub mod webhook;
+pub mod worker;
 
 pub fn get_config() -> Config {
     Rocket::ignite().config().clone()
@@ -0,0 +1,286 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::Utc;
+use diesel::PgConnection;
+use fehler::throws;
+use itertools::Itertools;
+
+#[cfg(feature = "alpm")]
+use crate::alpm;
+use crate::aur::batches;
+use crate::aur::get_aur_info;
+use crate::config::Aur;
+use crate::config::Retention;
+use crate::config::Settings;
+#[cfg(feature = "alpm")]
+use crate::config::Upstream;
+use crate::consistency::check_repo;
+use crate::consistency::Drift;
+use crate::consistency::unreferenced_files;
+use crate::db::create_aur_version;
+use crate::db::delete_aur_versions;
+use crate::db::models::NewAurVersion;
+use crate::db::models::Package;
+use crate::db::models::Repo;
+use crate::db::models::WebhookEvent;
+use crate::db::delete_finished_jobs;
+use crate::db::delete_sent_notifications;
+use crate::db::delete_webhook_deliveries;
+use crate::db::get_active_packages_by_name;
+use crate::db::get_aur_version;
+use crate::db::get_all_packages_by_repo;
+use crate::db::get_packages;
+use crate::db::get_repos;
+use crate::db::get_version_sources;
+use crate::db::set_package_deleted;
+use crate::db::set_version_source_result;
+use crate::error::Error;
+#[cfg(feature = "alpm")]
+use crate::jobs::create_check_deps;
+use crate::jobs::create_repo_action;
+use crate::jobs::RepoActionOp;
+use crate::notification::notify_outdated;
+use crate::notification::send_notifications;
+use crate::obsolete::determine_obsolete;
+use crate::vercmp::vercmp;
+use crate::version_source::latest_version;
+use crate::webhook::package_data;
+use crate::webhook::trigger_webhooks;
+
+/// The tags of the jobs that are enqueued by the scheduler and carried out by
+/// the maintenance worker.
+pub const TASKS: &[&str] = &["check_aur_versions", "check_upstream_versions", "sync_pacman_dbs", "send_notifications", "retention", "gc", "scrub", "consistency"];
+
+#[throws]
+pub fn run_task(conn: &PgConnection, settings: &Settings, task: &str) {
+    match task {
+        "check_aur_versions" => check_aur_versions(conn, &settings.aur)?,
+        "check_upstream_versions" => check_upstream_versions(conn)?,
+        #[cfg(feature = "alpm")]
+        "sync_pacman_dbs" => sync_pacman_dbs(conn, &settings.upstream, &settings.keyring)?,
+        #[cfg(not(feature = "alpm"))]
+        "sync_pacman_dbs" => Err("Syncing the upstream databases requires the alpm feature")?,
+        "send_notifications" => send_notifications(conn, settings.smtp.as_ref(), &settings.url)?,
+        "retention" => retention(conn, &settings.retention)?,
+        "gc" => gc(conn)?,
+        "scrub" => scrub(conn)?,
+        "consistency" => consistency(conn)?,
+        _ => Err(format!("Unknown maintenance task {}", task))?
+    }
+}
+
+/// The AUR rejects requests whose URL is longer than about 4 KiB.
+const AUR_MAX_QUERY_LEN: usize = 4000;
+
+/// Looks up every package in the AUR and stores the AUR version. When the AUR
+/// has a new version, outdated packages are reported to their owners and to
+/// the webhooks of their repositories. Packages that are no longer in the AUR
+/// are forgotten. If the AUR reports an error, or keeps rejecting requests
+/// because of its rate limit, the remaining packages are skipped.
+#[throws]
+pub fn check_aur_versions(conn: &PgConnection, aur: &Aur) {
+    let packages = get_packages(conn)?.into_iter()
+        .map(|p| p.name)
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    let packages = packages.iter()
+        .map(|p| p as &str)
+        .collect::<Vec<_>>();
+
+    for batch in batches(&packages, aur.batch_size, AUR_MAX_QUERY_LEN) {
+        let now = Utc::now().naive_utc();
+        let results = get_aur_info(aur, &batch)?;
+        for info in &results {
+            println!("{}... {}", info.name, info.version);
+            // Only a new AUR version is worth a notification, not every check.
+            let previous = get_aur_version(conn, &info.name)?;
+            if previous.map_or(true, |p| p.version != info.version) {
+                let packages = get_active_packages_by_name(conn, &info.name)?;
+                report_outdated(conn, packages, "aur", &info.version)?;
+            }
+            create_aur_version(conn, &NewAurVersion {
+                package: info.name.clone(),
+                version: info.version.clone(),
+                maintainer: info.maintainer.clone(),
+                out_of_date: info.out_of_date,
+                last_modified: Some(info.last_modified),
+                last_checked: now
+            })?;
+        }
+
+        let unknown = batch.iter()
+            .filter(|p| !results.iter().any(|r| r.name == **p))
+            .copied()
+            .collect::<Vec<_>>();
+        delete_aur_versions(conn, &unknown)?;
+    }
+}
+
+/// Notifies the owners of the packages that are older than the version found
+/// at the source, `aur` or the kind of a version source, and triggers the
+/// webhooks of their repositories.
+#[throws]
+fn report_outdated(conn: &PgConnection, packages: Vec<(Package, Repo)>, source: &str, version: &str) {
+    let mut reported = BTreeSet::new();
+    for (package, repo) in packages {
+        // Packages with several architectures are only reported once.
+        if vercmp(version, &package.version) == Ordering::Greater && reported.insert(repo.id) {
+            notify_outdated(conn, &repo, &package, source, version)?;
+            trigger_webhooks(conn, repo.id, WebhookEvent::UpstreamOutdated, serde_json::json!({
+                "package": package_data(&package),
+                "source": source,
+                "upstream_version": version
+            }))?;
+        }
+    }
+}
+
+/// Looks up the latest version of every package that has a version source,
+/// and reports packages that are outdated like `check_aur_versions` does.
+/// Failures are recorded with the version source instead of failing the task,
+/// since they are usually caused by the configuration of a single package.
+#[throws]
+pub fn check_upstream_versions(conn: &PgConnection) {
+    for version_source in get_version_sources(conn)? {
+        let result = latest_version(&version_source);
+        let now = Utc::now().naive_utc();
+        match result {
+            Ok(version) => {
+                println!("{}... {}", version_source.package, version);
+                if version_source.version.as_ref() != Some(&version) {
+                    let packages = get_active_packages_by_name(conn, &version_source.package)?.into_iter()
+                        .filter(|(_, repo)| repo.id == version_source.repo_id)
+                        .collect();
+                    report_outdated(conn, packages, &version_source.kind.to_string(), &version)?;
+                }
+                set_version_source_result(conn, version_source.id, Some(version), None, now)?;
+            }
+            Err(error) => {
+                let error = format!("{:#}", anyhow::Error::from(error));
+                println!("{}... failed: {}", version_source.package, error);
+                set_version_source_result(conn, version_source.id, None, Some(error), now)?;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alpm")]
+#[throws]
+pub fn sync_pacman_dbs(conn: &PgConnection, upstream: &Upstream, keyring: &str) {
+    let mut failures = Vec::new();
+    for arch in upstream.archs() {
+        match alpm::sync(arch, upstream.repos(arch), keyring) {
+            Ok(()) => println!("Synced {} repositories.", arch),
+            Err(error) => {
+                println!("Failed to sync {} repositories: {}", arch, error);
+                failures.push(error.to_string());
+            }
+        }
+    }
+
+    let repos = get_repos(conn)?;
+    for repo in repos {
+        create_check_deps(conn, repo.id)?;
+    }
+
+    // The previous databases are kept for the architectures that could not be
+    // synced, but the failure must not go unnoticed.
+    if !failures.is_empty() {
+        Err(format!("Kept previous databases, because syncing failed:\n{}", failures.join("\n")))?
+    }
+}
+
+/// Deletes old finished jobs, sent notifications and webhook deliveries and, if
+/// configured, obsolete packages.
+#[throws]
+pub fn retention(conn: &PgConnection, retention: &Retention) {
+    let now = Utc::now().naive_utc();
+
+    let finished_before = now - chrono::Duration::days(retention.finished_jobs as i64);
+    let deleted = delete_finished_jobs(conn, finished_before)?;
+    println!("Deleted {} finished jobs.", deleted);
+
+    let sent_before = now - chrono::Duration::days(retention.sent_notifications as i64);
+    let deleted = delete_sent_notifications(conn, sent_before)?;
+    println!("Deleted {} sent notifications.", deleted);
+
+    let created_before = now - chrono::Duration::days(retention.webhook_deliveries as i64);
+    let deleted = delete_webhook_deliveries(conn, created_before)?;
+    println!("Deleted {} webhook deliveries.", deleted);
+
+    if let Some(obsolete_packages) = retention.obsolete_packages {
+        let created_before = now - chrono::Duration::days(obsolete_packages as i64);
+        for repo in get_repos(conn)? {
+            let packages = get_all_packages_by_repo(conn, repo.id)?;
+            let obsoletes = determine_obsolete(packages.iter().collect());
+            for obsolete in obsoletes {
+                if obsolete.created < created_before {
+                    println!("Deleting obsolete package {:?}.", obsolete);
+                    set_package_deleted(conn, obsolete.id, true)?;
+                    create_repo_action(conn, obsolete.id, RepoActionOp::Remove)?;
+                }
+            }
+        }
+    }
+}
+
+/// Removes files from the package directory that no package refers to. Recent
+/// files are kept, because they may belong to an upload that is in progress.
+#[throws]
+pub fn gc(conn: &PgConnection) {
+    for drift in unreferenced_files(conn, Duration::from_secs(60 * 60))? {
+        if let Drift::Unreferenced { file } = drift {
+            println!("Removing unreferenced file {}.", file);
+            fs::remove_file(Path::new("packages").join(file))?;
+        }
+    }
+}
+
+/// Checks that the archive and signature of every package are present and that
+/// their sizes match the size that was recorded on upload.
+#[throws]
+pub fn scrub(conn: &PgConnection) {
+    let mut problems = Vec::new();
+    for package in get_packages(conn)? {
+        let archive = fs::metadata(Path::new("packages").join(&package.archive));
+        let signature = fs::metadata(Path::new("packages").join(&package.signature));
+        match (archive, signature) {
+            (Ok(archive), Ok(signature)) => {
+                if archive.len() + signature.len() != package.size as u64 {
+                    problems.push(format!("{}-{}: size on disk differs from recorded size",
+                        package.name, package.version));
+                }
+            }
+            (Err(_), _) => problems.push(format!("{}-{}: archive {} is missing",
+                package.name, package.version, package.archive)),
+            (_, Err(_)) => problems.push(format!("{}-{}: signature {} is missing",
+                package.name, package.version, package.signature)),
+        }
+    }
+
+    if !problems.is_empty() {
+        Err(format!("Found {} problems:\n{}", problems.len(), problems.join("\n")))?
+    }
+}
+
+/// Checks that the database of every repository matches its active packages.
+/// The drift is only reported; a rebuild of the affected repositories has to
+/// be requested with `pb-check-repos --rebuild`.
+#[throws]
+pub fn consistency(conn: &PgConnection) {
+    let mut problems = Vec::new();
+    for repo in get_repos(conn)? {
+        for drift in check_repo(conn, repo.id)? {
+            problems.push(format!("repo {} ({}): {}", repo.name, repo.id, drift));
+        }
+    }
+
+    if !problems.is_empty() {
+        Err(format!("Found {} problems:\n{}", problems.len(), problems.join("\n")))?
+    }
+}
@@ -0,0 +1,135 @@
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use fehler::throws;
+use reqwest::blocking::Client;
+use reqwest::blocking::Response;
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+
+use crate::error::Error;
+
+/// How many redirects are followed before a request fails.
+const MAX_REDIRECTS: usize = 10;
+
+/// Whether the address is reachable on the internet, as opposed to loopback,
+/// private, link-local and other special purpose addresses that would give
+/// users access to our own network.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4() {
+            // Mapped and compatible addresses, except for the loopback and
+            // unspecified address, which are IPv6 addresses of their own.
+            Some(v4) if !ip.is_loopback() && !ip.is_unspecified() => is_public_v4(v4),
+            _ => is_public_v6(ip)
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && b & 0xc0 == 64)  // Shared address space (100.64.0.0/10)
+        || (a == 192 && b == 0 && c == 0)  // IETF protocol assignments
+        || (a == 198 && b & 0xfe == 18)  // Benchmarking (198.18.0.0/15)
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || first & 0xfe00 == 0xfc00  // Unique local (fc00::/7)
+        || first & 0xffc0 == 0xfe80  // Link-local (fe80::/10)
+        || (first == 0x2001 && ip.segments()[1] == 0xdb8))  // Documentation
+}
+
+/// Fails unless the URL is an HTTP(S) URL whose host resolves to public
+/// addresses only. Requests on behalf of users have to pass this check, so
+/// they can't be used to reach services that are only meant to be reachable
+/// from our own hosts. Returns the address that the request has to be sent
+/// to, since resolving the host again may give a different one.
+#[throws]
+pub fn check_public_url(url: &Url) -> SocketAddr {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        Err(format!("{} is not an HTTP(S) URL", url))?
+    }
+    let addrs = url.socket_addrs(|| None)
+        .map_err(|e| format!("Failed to resolve {}: {}", url, e))?;
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public(addr.ip())) {
+        Err(format!("{} does not resolve to a public address", url))?
+    }
+    addrs[0]
+}
+
+/// A client for requests to the URL, which connects to the address that its
+/// host was checked to resolve to. Redirects are not followed, since they
+/// would be resolved by the client itself.
+#[throws]
+pub fn public_client(url: &Url, timeout: Duration) -> Client {
+    let addr = check_public_url(url)?;
+    let mut builder = Client::builder()
+        .user_agent("paketbote")
+        .timeout(timeout)
+        .redirect(Policy::none());
+    if let Some(domain) = url.domain() {
+        builder = builder.resolve(domain, addr);
+    }
+    builder.build()?
+}
+
+/// Sends a GET request to the URL and follows redirects, as long as they lead
+/// to public addresses. Every hop is checked and pinned like the first one.
+#[throws]
+pub fn get_public(url: Url, timeout: Duration) -> Response {
+    let mut url = url;
+    for _ in 0..=MAX_REDIRECTS {
+        let response = public_client(&url, timeout)?.get(url.clone()).send()?;
+        let location = Some(&response)
+            .filter(|r| r.status().is_redirection())
+            .and_then(|r| r.headers().get(LOCATION))
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        match location {
+            Some(next) => url = next,
+            None => return response
+        }
+    }
+    Err("Too many redirects")?
+}
+
+#[test]
+fn test_is_public() {
+    let public = |ip: &str| is_public(ip.parse().unwrap());
+    assert!(public("1.1.1.1"));
+    assert!(public("95.216.10.1"));
+    assert!(public("2a01:4f9::1"));
+    assert!(public("::ffff:1.1.1.1"));
+    assert!(!public("127.0.0.1"));
+    assert!(!public("10.1.2.3"));
+    assert!(!public("172.16.0.1"));
+    assert!(!public("192.168.1.1"));
+    assert!(!public("169.254.169.254"));
+    assert!(!public("100.64.0.1"));
+    assert!(!public("0.0.0.0"));
+    assert!(!public("255.255.255.255"));
+    assert!(!public("::1"));
+    assert!(!public("::"));
+    assert!(!public("fd00::1"));
+    assert!(!public("fe80::1"));
+    assert!(!public("::ffff:127.0.0.1"));
+    assert!(!public("::ffff:10.0.0.1"));
+}
@@ -0,0 +1,265 @@
+use chrono::Utc;
+use diesel::PgConnection;
+use fehler::throws;
+use itertools::Itertools;
+use lettre::ClientSecurity;
+use lettre::ClientTlsParameters;
+use lettre::smtp::authentication::Credentials;
+use lettre::SmtpClient;
+use lettre::SmtpTransport;
+use lettre::Transport;
+use lettre_email::EmailBuilder;
+use native_tls::TlsConnector;
+
+use crate::config::Smtp;
+use crate::db::create_notification;
+use crate::db::get_job_by_id;
+use crate::db::get_notification_preference;
+use crate::db::get_repo;
+use crate::db::get_unconfirmed_notification_preferences;
+use crate::db::get_unsent_notifications;
+use crate::db::models::NewNotification;
+use crate::db::models::Notification;
+use crate::db::models::NotificationKind;
+use crate::db::models::NotificationPreference;
+use crate::db::models::Package;
+use crate::db::models::Repo;
+use crate::db::set_confirmation_sent;
+use crate::db::set_confirmation_token;
+use crate::db::set_notifications_sent;
+use crate::error::Error;
+use crate::missing::MissingDepsChange;
+
+/// How long (in minutes) the web interface waits before it sends another
+/// confirmation link to an account.
+pub const CONFIRMATION_COOLDOWN_MINUTES: i64 = 15;
+
+/// Queues a notification for the account, unless the account did not ask for
+/// notifications of this kind. Queued notifications are sent as a digest by
+/// the `send_notifications` task.
+#[throws]
+pub fn notify(conn: &PgConnection, account_id: i32, kind: NotificationKind, message: String) {
+    if let Some(preference) = get_notification_preference(conn, account_id)? {
+        if preference.wants(kind) {
+            create_notification(conn, &NewNotification { account_id, kind, message })?;
+        }
+    }
+}
+
+/// Notifies the owner of the repository that the source, `aur` or the kind of
+/// a version source, has a newer version of the package.
+#[throws]
+pub fn notify_outdated(conn: &PgConnection, repo: &Repo, package: &Package, source: &str, version: &str) {
+    notify(conn, repo.owner_id, NotificationKind::Outdated, outdated_message(repo, package, source, version))?;
+}
+
+fn outdated_message(repo: &Repo, package: &Package, source: &str, version: &str) -> String {
+    let source = match source {
+        "aur" => "the AUR".to_owned(),
+        kind => format!("its {} version source", kind)
+    };
+    format!("{}: {} {} is outdated, {} has {}", repo.name, package.name, package.version, source, version)
+}
+
+/// Notifies the owner of the repository that its missing dependencies changed.
+#[throws]
+pub fn notify_missing_deps(conn: &PgConnection, repo_id: i32, change: &MissingDepsChange) {
+    let repo = get_repo(conn, repo_id)?;
+    if let Some(message) = missing_deps_message(&repo.name, change) {
+        notify(conn, repo.owner_id, NotificationKind::MissingDeps, message)?;
+    }
+}
+
+fn missing_deps_message(repo: &str, change: &MissingDepsChange) -> Option<String> {
+    if change.is_empty() {
+        return None;
+    }
+    let mut message = format!("{}: the missing dependencies changed", repo);
+    if !change.added.is_empty() {
+        message += &format!("\n  now missing: {}", change.added.iter().join(", "));
+    }
+    if !change.removed.is_empty() {
+        message += &format!("\n  no longer missing: {}", change.removed.iter().join(", "));
+    }
+    Some(message)
+}
+
+/// Notifies the owner of the repository that the job failed. Jobs that do not
+/// belong to a repository, like the maintenance tasks, are only shown to the
+/// admins.
+#[throws]
+pub fn notify_failed_job(conn: &PgConnection, job_id: i32, error: &str) {
+    if let Some(job) = get_job_by_id(conn, job_id)? {
+        if let Some(repo_id) = job.repo_id {
+            let repo = get_repo(conn, repo_id)?;
+            let message = format!("{}: job {} ({}) failed: {}", repo.name, job.id, job.tag, error);
+            notify(conn, repo.owner_id, NotificationKind::FailedJob, message)?;
+        }
+    }
+}
+
+/// Sends the account a link to `url` that confirms its email address. Every
+/// link gets a new token, so only the latest one works.
+#[throws]
+pub fn send_confirmation(conn: &PgConnection, smtp: &Smtp, url: &str, preference: &NotificationPreference) {
+    let token = create_confirmation_token();
+    let link = format!("{}/notifications/confirm/{}", url.trim_end_matches('/'), token);
+    let email = EmailBuilder::new()
+        .to(preference.email.clone())
+        .from(smtp.from.clone())
+        .subject("paketbote: confirm your email address")
+        .text(format!("Open this link to get notifications from paketbote at this address:\n\n{}\n", link))
+        .build()
+        .map_err(|e| format!("Failed to create the confirmation for {}: {}", preference.email, e))?;
+    set_confirmation_token(conn, preference.account_id, &token)?;
+    transport(smtp)?.send(email.into())
+        .map_err(|e| format!("Failed to send the confirmation to {}: {}", preference.email, e))?;
+    set_confirmation_sent(conn, preference.account_id, Utc::now().naive_utc())?;
+}
+
+fn create_confirmation_token() -> String {
+    rand::random::<[u8; 20]>().iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Sends the confirmation links that have not been sent yet, and every
+/// confirmed account with queued notifications a digest of them. The
+/// notifications of accounts that turned notifications off or changed their
+/// address in the meantime are dropped. If sending a digest fails, it is sent
+/// again on the next run.
+#[throws]
+pub fn send_notifications(conn: &PgConnection, smtp: Option<&Smtp>, url: &str) {
+    let unconfirmed = get_unconfirmed_notification_preferences(conn)?;
+    let notifications = get_unsent_notifications(conn)?;
+    if !unconfirmed.is_empty() || !notifications.is_empty() {
+        let smtp = smtp.ok_or("Emails are queued, but no SMTP server is configured")?;
+
+        let mut failures = Vec::new();
+        for preference in &unconfirmed {
+            if let Err(error) = send_confirmation(conn, smtp, url, preference) {
+                println!("{}", error);
+                failures.push(error.to_string());
+            }
+        }
+
+        let mut transport = transport(smtp)?;
+        let by_account = notifications.into_iter().group_by(|n| n.account_id);
+        for (account_id, notifications) in &by_account {
+            let notifications = notifications.collect::<Vec<_>>();
+            let ids = notifications.iter().map(|n| n.id).collect::<Vec<_>>();
+            let preference = get_notification_preference(conn, account_id)?
+                .filter(|p| p.confirmed);
+            if let Some(preference) = preference {
+                let email = EmailBuilder::new()
+                    .to(preference.email.clone())
+                    .from(smtp.from.clone())
+                    .subject(format!("paketbote: {} new notifications", notifications.len()))
+                    .text(digest(&notifications))
+                    .build()
+                    .map_err(|e| format!("Failed to create the digest for {}: {}", preference.email, e))?;
+                if let Err(error) = transport.send(email.into()) {
+                    println!("Failed to send the digest to {}: {}", preference.email, error);
+                    failures.push(format!("{}: {}", preference.email, error));
+                    continue;
+                }
+                println!("Sent {} notifications to {}.", notifications.len(), preference.email);
+            }
+            set_notifications_sent(conn, &ids, Utc::now().naive_utc())?;
+        }
+
+        if !failures.is_empty() {
+            Err(format!("Failed to send {} emails:\n{}", failures.len(), failures.join("\n")))?
+        }
+    }
+}
+
+#[throws]
+fn transport(smtp: &Smtp) -> SmtpTransport {
+    let security = if smtp.tls {
+        let connector = TlsConnector::new()
+            .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+        ClientSecurity::Required(ClientTlsParameters::new(smtp.host.clone(), connector))
+    } else {
+        ClientSecurity::None
+    };
+    let mut client = SmtpClient::new((smtp.host.as_str(), smtp.port), security)
+        .map_err(|e| format!("Failed to resolve SMTP server {}: {}", smtp.host, e))?;
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        client = client.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    client.transport()
+}
+
+/// The text of a digest, with the notifications grouped by kind.
+fn digest(notifications: &[Notification]) -> String {
+    let mut notifications = notifications.iter().collect::<Vec<_>>();
+    notifications.sort_by_key(|n| n.kind);
+    let mut digest = String::new();
+    for (kind, notifications) in &notifications.into_iter().group_by(|n| n.kind) {
+        let heading = match kind {
+            NotificationKind::Outdated => "Outdated packages",
+            NotificationKind::MissingDeps => "Missing dependencies",
+            NotificationKind::FailedJob => "Failed jobs"
+        };
+        if !digest.is_empty() {
+            digest.push('\n');
+        }
+        digest += &format!("{}\n\n", heading);
+        for notification in notifications {
+            digest += &format!("{}\n", notification.message);
+        }
+    }
+    digest
+}
+
+#[test]
+fn test_missing_deps_message() {
+    use crate::db::models::DependencyKind;
+    use crate::missing::MissingSpec;
+
+    let spec = |dependency: &str, arch: &str, kind| MissingSpec {
+        dependency: dependency.to_owned(),
+        arch: arch.to_owned(),
+        kind
+    };
+    assert_eq!(missing_deps_message("repo", &MissingDepsChange::default()), None);
+    let change = MissingDepsChange {
+        added: vec![spec("c", "aarch64", DependencyKind::Depend)],
+        removed: vec![spec("b", "x86_64", DependencyKind::MakeDepend)]
+    };
+    assert_eq!(
+        missing_deps_message("repo", &change).unwrap(),
+        "repo: the missing dependencies changed\n  now missing: c (aarch64)\n  no longer missing: b (x86_64, makedepend)");
+    let change = MissingDepsChange {
+        added: vec![spec("a", "x86_64", DependencyKind::Depend), spec("b", "x86_64", DependencyKind::MakeDepend)],
+        removed: vec![]
+    };
+    assert_eq!(
+        missing_deps_message("repo", &change).unwrap(),
+        "repo: the missing dependencies changed\n  now missing: a (x86_64), b (x86_64, makedepend)");
+}
+
+#[test]
+fn test_digest() {
+    let notification = |id, kind, message: &str| Notification {
+        id,
+        account_id: 1,
+        kind,
+        message: message.to_owned(),
+        created: chrono::NaiveDateTime::from_timestamp(0, 0),
+        sent: None
+    };
+    let notifications = [
+        notification(1, NotificationKind::FailedJob, "repo: job 3 (repo_action) failed"),
+        notification(2, NotificationKind::Outdated, "repo: a 1-1 is outdated, the AUR has 2-1"),
+        notification(3, NotificationKind::Outdated, "repo: b 1-1 is outdated, the AUR has 3-1"),
+    ];
+    assert_eq!(digest(&notifications), "\
+        Outdated packages\n\n\
+        repo: a 1-1 is outdated, the AUR has 2-1\n\
+        repo: b 1-1 is outdated, the AUR has 3-1\n\
+        \n\
+        Failed jobs\n\n\
+        repo: job 3 (repo_action) failed\n");
+}
@@ -1,7 +1,7 @@
-use alpm::vercmp;
 use itertools::Itertools;
 
 use crate::db::models::Package;
+use crate::vercmp::vercmp;
 
 pub fn determine_obsolete(mut packages: Vec<&Package>) -> Vec<&Package> {
     packages.sort_by_key(|p| p.name.clone());
@@ -90,7 +90,8 @@ mod test {
             .and_hms(9, 10, 11);
         let deleted = false;
         let repo_id = 0;
-        Package { id, name, version, arch, size, archive, signature, compression, created, active, deleted, repo_id }
+        let description = None;
+        Package { id, name, version, arch, size, archive, signature, compression, created, active, deleted, repo_id, description }
     }
 
     fn get_ids(packages: &[&Package]) -> Vec<i32> {
@@ -0,0 +1,136 @@
+use diesel::PgConnection;
+use fehler::throws;
+use serde::Serialize;
+
+use crate::arch::arch_matches;
+use crate::arch::repo_archs;
+use crate::db::get_depends_by_repo;
+use crate::db::get_provides_by_repo;
+use crate::db::models::DependencyKind;
+use crate::db::models::Package;
+use crate::error::Error;
+use crate::missing::layered_repos;
+use crate::missing::provider_repos;
+use crate::spec::Spec;
+use crate::syncdb::UpstreamDbs;
+
+/// A dependency of an active package that is satisfied by another package.
+#[derive(Debug, Clone, Serialize)]
+pub struct Requirement {
+    pub package_id: i32,
+    pub dependency: String,
+}
+
+/// A dependency of an active package that is no longer satisfied if some
+/// packages are removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Breakage {
+    pub package_id: i32,
+    pub arch: String,
+    pub dependency: String,
+}
+
+struct Provided {
+    package_id: i32,
+    arch: String,
+    spec: Spec,
+}
+
+struct Required {
+    package_id: i32,
+    arch: String,
+    spec: Spec,
+}
+
+#[throws]
+fn load_provided(conn: &PgConnection, repo_id: i32) -> Vec<Provided> {
+    let mut provided = Vec::new();
+    for provider_id in provider_repos(conn, repo_id)? {
+        for (package_id, arch, pp) in get_provides_by_repo(conn, provider_id)? {
+            let spec: Spec = pp.parse()?;
+            provided.push(Provided { package_id, arch, spec });
+        }
+    }
+    provided
+}
+
+#[throws]
+fn load_required(conn: &PgConnection, repo_id: i32) -> Vec<Required> {
+    get_depends_by_repo(conn, repo_id, DependencyKind::Depend)?.into_iter()
+        .map(|(package_id, arch, pd)| -> Result<Required, Error> {
+            Ok(Required { package_id, arch, spec: pd.parse()? })
+        })
+        .collect::<Result<Vec<_>, _>>()?
+}
+
+/// Returns the dependencies of active packages, in the repository of the
+/// package and in the repositories that layer on it, that the package
+/// satisfies.
+#[throws]
+pub fn required_by(conn: &PgConnection, package: &Package) -> Vec<Requirement> {
+    let provided = get_provides_by_repo(conn, package.repo_id)?.into_iter()
+        .filter(|(package_id, _, _)| *package_id == package.id)
+        .map(|(_, _, pp)| -> Result<Spec, Error> { Ok(pp.parse()?) })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut requirements = Vec::new();
+    for repo_id in layered_repos(conn, package.repo_id)? {
+        for required in load_required(conn, repo_id)? {
+            if required.package_id != package.id
+                && arch_matches(&package.arch, &required.arch)
+                && provided.iter().any(|p| p.satisfies(&required.spec)) {
+                requirements.push(Requirement {
+                    package_id: required.package_id,
+                    dependency: required.spec.to_string()
+                });
+            }
+        }
+    }
+    requirements
+}
+
+/// Determines which dependencies would no longer be satisfied if the given
+/// packages of the repository were removed. Only dependencies that one of the
+/// removed packages satisfies and that neither another package nor an upstream
+/// repository satisfies are reported.
+#[throws]
+pub fn removal_impact(conn: &PgConnection, upstream: &UpstreamDbs, repo_id: i32, removed: &[i32]) -> Vec<Breakage> {
+    let mut breakages = Vec::new();
+    for affected_id in layered_repos(conn, repo_id)? {
+        let provided = load_provided(conn, affected_id)?;
+        let required = load_required(conn, affected_id)?;
+
+        let archs = repo_archs(conn, affected_id)?;
+
+        for arch in &archs {
+            let applies = |a: &str| arch_matches(a, arch);
+            for required in required.iter().filter(|r| applies(&r.arch)) {
+                if removed.contains(&required.package_id) {
+                    continue;
+                }
+                let satisfiers = provided.iter()
+                    .filter(|p| applies(&p.arch) && p.spec.satisfies(&required.spec))
+                    .collect::<Vec<_>>();
+                let lost = satisfiers.iter().any(|p| removed.contains(&p.package_id));
+                let kept = satisfiers.iter().any(|p| !removed.contains(&p.package_id));
+                if !lost || kept {
+                    continue;
+                }
+
+                if upstream.get(arch)?.find_satisfier(&required.spec.to_string()).is_some() {
+                    continue;
+                }
+
+                let breakage = Breakage {
+                    package_id: required.package_id,
+                    arch: arch.clone(),
+                    dependency: required.spec.to_string()
+                };
+                if !breakages.contains(&breakage) {
+                    breakages.push(breakage);
+                }
+            }
+        }
+    }
+    breakages
+}
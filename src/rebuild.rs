@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use diesel::PgConnection;
+use fehler::throws;
+
+use crate::arch::arch_matches;
+use crate::db::get_provides_by_repo;
+use crate::db::models::DependencyKind;
+use crate::error::Error;
+use crate::graph::build_stages;
+use crate::graph::dependency_graph;
+use crate::graph::package_node;
+use crate::missing::MissingDependency;
+use crate::missing::provider_repos;
+use crate::spec::Spec;
+use crate::syncdb::UpstreamDbs;
+
+/// A versioned dependency of a package that is no longer satisfied, although
+/// something of the same name is still available. This usually means that the
+/// dependency was updated, e.g. to a new soname or a new major version, and
+/// the package has to be rebuilt against it.
+#[derive(Debug)]
+pub struct NeededRebuild {
+    pub package_id: i32,
+    pub arch: String,
+    pub dependency: String,
+    /// What satisfies the dependency if the version is ignored.
+    pub available: String,
+}
+
+/// Determines which of the missing dependencies of the repository call for a
+/// rebuild of the packages that need them.
+#[throws]
+pub fn needed_rebuilds(
+    conn: &PgConnection,
+    repo_id: i32,
+    upstream: &UpstreamDbs,
+    missing: &[MissingDependency]
+) -> Vec<NeededRebuild> {
+    let mut provides = Vec::new();
+    for provider_id in provider_repos(conn, repo_id)? {
+        for (_, arch, pp) in get_provides_by_repo(conn, provider_id)? {
+            let spec: Spec = pp.parse()?;
+            provides.push((arch, spec));
+        }
+    }
+
+    let mut rebuilds = Vec::new();
+    for missing in missing {
+        if missing.kind != DependencyKind::Depend || missing.dependency.version.is_none() {
+            continue;
+        }
+        let name = &missing.dependency.name;
+
+        let local = provides.iter()
+            .find(|(a, p)| arch_matches(a, &missing.arch) && p.name == *name)
+            .map(|(_, p)| p.to_string());
+        let available = match local {
+            Some(local) => Some(local),
+            None => upstream.get(&missing.arch)?.find_satisfier(name)
+                .map(|(_, pkg)| format!("{}={}", pkg.name, pkg.version))
+        };
+
+        if let Some(available) = available {
+            rebuilds.push(NeededRebuild {
+                package_id: missing.package_id,
+                arch: missing.arch.clone(),
+                dependency: missing.dependency.to_string(),
+                available
+            });
+        }
+    }
+    rebuilds
+}
+
+/// Orders the given packages of the repository into stages, such that every
+/// package is rebuilt after the packages of the repository it depends on.
+#[throws]
+pub fn rebuild_plan(conn: &PgConnection, repo_id: i32, package_ids: &[i32]) -> Vec<Vec<i32>> {
+    let graph = dependency_graph(conn, repo_id, None)?;
+    let ids = package_ids.iter()
+        .map(|id| (package_node(*id), *id))
+        .collect::<HashMap<_, _>>();
+    let nodes = ids.keys().cloned().collect::<Vec<_>>();
+    build_stages(&nodes, &graph.edges).into_iter()
+        .map(|stage| stage.iter().map(|node| ids[node]).collect())
+        .collect()
+}
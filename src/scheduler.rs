@@ -0,0 +1,38 @@
+use chrono::Utc;
+use diesel::PgConnection;
+use fehler::throws;
+use serde_json::json;
+
+use crate::config::Schedule;
+use crate::db::claim_periodic_task;
+use crate::db::create_job;
+use crate::db::has_open_job;
+use crate::db::register_periodic_task;
+use crate::error::Error;
+
+/// Enqueues a job for every periodic task that is due. Several workers may run
+/// the scheduler at the same time: a run is only enqueued by the worker that
+/// managed to claim it, and not at all while the previous run is still queued.
+#[throws]
+pub fn run_due_tasks(conn: &PgConnection, schedule: &Schedule) {
+    for (name, period) in schedule.tasks() {
+        if period == 0 {
+            continue;
+        }
+
+        let now = Utc::now().naive_utc();
+        let next_run = now + chrono::Duration::seconds(period as i64);
+        register_periodic_task(conn, name, period as i32, next_run)?;
+
+        if !claim_periodic_task(conn, name, now, next_run)? {
+            continue;
+        }
+
+        if has_open_job(conn, name)? {
+            println!("Skipping periodic task {}, because it is still queued.", name);
+        } else {
+            println!("Enqueuing periodic task {}.", name);
+            create_job(conn, name.to_owned(), json!({}), None, None)?;
+        }
+    }
+}
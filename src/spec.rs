@@ -2,32 +2,33 @@ use std::cmp::Ordering;
 use std::fmt::Display;
 use std::str::FromStr;
 
-use alpm::vercmp;
-use regex::Regex;
 use serde::export::Formatter;
 
 use crate::error::Error;
+use crate::vercmp::vercmp;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+/// An entry of the `depend`, `optdepend`, `provides` or `conflict` fields of a
+/// package, e.g. `python>=3.8`, `libfoo.so=2-64` or `gtk3: for the GUI`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Spec {
     pub name: String,
     pub version: Option<Version>,
+    /// The description of an optional dependency.
+    pub description: Option<String>,
 }
 
 impl Spec {
     pub fn new(name: String, version: Version) -> Spec {
-        Spec { name, version: Some(version) }
+        Spec { name, version: Some(version), description: None }
     }
 
     pub fn new_without_version(name: String) -> Spec {
-        Spec { name, version: None }
-    }
-
-    pub fn fallback_version(self, default: Version) -> Spec {
-        let version = self.version.unwrap_or(default);
-        Spec { version: Some(version), ..self }
+        Spec { name, version: None, description: None }
     }
 
+    /// Whether this provision satisfies the dependency. Like libalpm, an
+    /// unversioned provision only satisfies unversioned dependencies, and a
+    /// versioned provision must use `=`.
     pub fn satisfies(&self, depends: &Spec) -> bool {
         let provides = self;
 
@@ -36,8 +37,9 @@ impl Spec {
         }
 
         match (&depends.version, &provides.version) {
-            (Some(depends), Some(provides)) => provides.satisfies(depends),
-            _ => true
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(depends), Some(provides)) => provides.satisfies(depends)
         }
     }
 }
@@ -45,32 +47,47 @@ impl Spec {
 impl FromStr for Spec {
     type Err = Error;
     fn from_str(spec: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new("([^<>=]*)([<>]?=|[<>]=?)(.*)").unwrap();
-        match re.captures(spec) {
-            Some(captures) => {
-                let name = captures.get(1).unwrap().as_str().to_owned();
-                let relation: Relation = captures.get(2).unwrap().as_str().parse()?;
-                let version = captures.get(3).unwrap().as_str().to_owned();
-                let version = Version::new(version, relation);
-                Ok(Spec::new(name, version))
+        let (spec, description) = match spec.find(": ") {
+            Some(i) => (&spec[..i], Some(spec[i + 2..].to_owned())),
+            None => (spec, None)
+        };
+
+        let (name, version) = match spec.find(|c| c == '<' || c == '>' || c == '=') {
+            Some(i) => {
+                let rest = &spec[i..];
+                let len = if rest.starts_with(">=") || rest.starts_with("<=") { 2 } else { 1 };
+                let relation: Relation = rest[..len].parse()?;
+                let version = &rest[len..];
+                if version.is_empty() {
+                    Err(format!("missing version in {}", spec))?
+                }
+                (&spec[..i], Some(Version::new(version.to_owned(), relation)))
             }
-            None =>
-                Ok(Spec::new_without_version(spec.to_owned()))
+            None => (spec, None)
+        };
+
+        if name.is_empty() {
+            Err(format!("missing name in {}", spec))?
         }
+
+        Ok(Spec { name: name.to_owned(), version, description })
     }
 }
 
 impl Display for Spec {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
         if let Some(version) = &self.version {
-            write!(f, "{}{}", self.name, version)
-        } else {
-            write!(f, "{}", self.name)
+            write!(f, "{}", version)?;
+        }
+        if let Some(description) = &self.description {
+            write!(f, ": {}", description)?;
         }
+        Ok(())
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Version {
     pub version: String,
     pub relation: Relation,
@@ -85,31 +102,22 @@ impl Version {
         Version::new(version, Relation::Eq)
     }
 
+    /// Whether this provided version satisfies the version of a dependency.
+    /// Versions are compared with `vercmp`, so epochs are taken into account
+    /// and the pkgrel is ignored if either side has none. Provisions that do
+    /// not use `=` satisfy nothing.
     pub fn satisfies(&self, depends: &Version) -> bool {
         use Relation::*;
-        assert_ne!(self.relation, Less, "Provides version with less than relation.");
-        assert_ne!(self.relation, Greater, "Provides version with greater than relation.");
+        if self.relation != Eq {
+            return false;
+        }
         let ord = vercmp(&self.version, &depends.version);
-        match (&self.relation, &depends.relation) {
-            (_, Eq) =>
-                ord == Ordering::Equal,
-            (LessEq, Greater) =>
-                ord == Ordering::Greater,
-            (LessEq, GreaterEq) =>
-                ord == Ordering::Greater || ord == Ordering::Equal,
-            (Eq, Less) =>
-                ord == Ordering::Less,
-            (Eq, LessEq) =>
-                ord == Ordering::Less || ord == Ordering::Equal,
-            (Eq, Greater) =>
-                ord == Ordering::Greater,
-            (Eq, GreaterEq) =>
-                ord == Ordering::Greater || ord == Ordering::Equal,
-            (GreaterEq, Less) =>
-                ord == Ordering::Less,
-            (GreaterEq, LessEq) =>
-                ord == Ordering::Less || ord == Ordering::Equal,
-            _ => true
+        match depends.relation {
+            Less => ord == Ordering::Less,
+            LessEq => ord != Ordering::Greater,
+            Eq => ord == Ordering::Equal,
+            GreaterEq => ord != Ordering::Less,
+            Greater => ord == Ordering::Greater
         }
     }
 }
@@ -120,7 +128,7 @@ impl Display for Version {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Relation {
     Less,
     LessEq,
@@ -171,14 +179,148 @@ fn test_satisfies() {
     }
 
     test("package", "package", true);
-    test("package", "package>=1.0", true);
-    test("package>=1.0", "package", true);
-    test("package>=1.0", "package>=0.3", true);
-    test("package>=1.0", "package>=1.3", true);
-    test("package<=1.0", "package>=1.3", false);
-    test("package<=1.3", "package>=1.3", true);
-    test("package<1.3", "package>=1.3", false);
+    test("package", "package=1.0", true);
+    test("package>=1.0", "package", false);
+    test("package>=1.0", "package=1.3", true);
+    test("package<=1.0", "package=1.3", false);
+    test("package<=1.3", "package=1.3", true);
+    test("package<1.3", "package=1.3", false);
     test("package>=1.3", "package=1.3.2", true);
     test("package>1.3", "package=1.3.2", true);
     test("package>1.3.2", "package=1.3.2", false);
+    test("package=1.3", "package=1.3-2", true);
+    test("package=1.3-1", "package=1.3-2", false);
+    test("package>=1:1.0", "package=2.0", false);
+    test("package>=1:1.0", "package=1:1.0-1", true);
+    test("package", "package<1.0", true);
+    test("package>=1.0", "package>=1.3", false);
+    test("package<2.0", "package<1.0", false);
+    test("package", "other", false);
+}
+
+#[test]
+fn test_parse() {
+    fn test(spec: &str, name: &str, version: Option<(Relation, &str)>, description: Option<&str>) {
+        let parsed: Spec = spec.parse().unwrap();
+        assert_eq!(parsed.name, name);
+        assert_eq!(parsed.version, version.map(|(r, v)| Version::new(v.to_owned(), r)));
+        assert_eq!(parsed.description.as_deref(), description);
+        assert_eq!(parsed.to_string(), spec);
+    }
+
+    test("package", "package", None, None);
+    test("package>=1.0", "package", Some((Relation::GreaterEq, "1.0")), None);
+    test("package<1:2.0-3", "package", Some((Relation::Less, "1:2.0-3")), None);
+    test("libfoo.so=2-64", "libfoo.so", Some((Relation::Eq, "2-64")), None);
+    test("gtk3: for the GUI", "gtk3", None, Some("for the GUI"));
+    test("python>=3.8: scripting", "python", Some((Relation::GreaterEq, "3.8")), Some("scripting"));
+
+    assert!("".parse::<Spec>().is_err());
+    assert!(">=1.0".parse::<Spec>().is_err());
+    assert!("package>=".parse::<Spec>().is_err());
+}
+
+#[cfg(test)]
+mod proptests {
+    use std::cmp::Ordering;
+
+    use proptest::prelude::*;
+
+    use crate::vercmp::vercmp;
+
+    use super::Spec;
+
+    fn version() -> impl Strategy<Value = String> {
+        "([1-2]:)?[0-9]{1,2}(\\.[0-9a-z]{1,2}){0,2}(-[1-3])?"
+    }
+
+    fn relation() -> impl Strategy<Value = &'static str> {
+        prop_oneof![Just("<"), Just("<="), Just("="), Just(">="), Just(">")]
+    }
+
+    fn spec(name: &'static str) -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just(name.to_owned()),
+            (relation(), version()).prop_map(move |(r, v)| format!("{}{}{}", name, r, v))
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn satisfies_agrees_with_vercmp(a in version(), b in version(), r in relation()) {
+            let provides: Spec = format!("provision={}", a).parse().unwrap();
+            let depends: Spec = format!("provision{}{}", r, b).parse().unwrap();
+            let ord = vercmp(&a, &b);
+            let expected = match r {
+                "<" => ord == Ordering::Less,
+                "<=" => ord != Ordering::Greater,
+                "=" => ord == Ordering::Equal,
+                ">=" => ord != Ordering::Less,
+                _ => ord == Ordering::Greater
+            };
+            prop_assert_eq!(provides.satisfies(&depends), expected);
+        }
+
+        #[test]
+        fn parse_roundtrips(s in spec("provision"), description in proptest::option::of("[a-z ]{1,10}")) {
+            let s = match description {
+                Some(description) => format!("{}: {}", s, description),
+                None => s
+            };
+            let parsed: Spec = s.parse().unwrap();
+            prop_assert_eq!(parsed.to_string(), s);
+        }
+    }
+
+    #[cfg(feature = "alpm")]
+    mod libalpm {
+        use std::env;
+        use std::fs;
+        use std::fs::File;
+        use std::path::Path;
+
+        use alpm::Alpm;
+        use alpm::SigLevel;
+        use proptest::prelude::*;
+
+        use super::spec;
+        use super::super::Spec;
+
+        /// Creates a sync database with a single package that provides
+        /// `provides`.
+        fn create_alpm(dir: &Path, provides: &str) -> Alpm {
+            if dir.exists() {
+                fs::remove_dir_all(dir).unwrap();
+            }
+            fs::create_dir_all(dir.join("sync")).unwrap();
+
+            let desc = format!(
+                "%FILENAME%\npackage-1-1-any.pkg.tar.zst\n\n%NAME%\npackage\n\n%VERSION%\n1-1\n\n\
+                 %ARCH%\nany\n\n%PROVIDES%\n{}\n\n", provides);
+            let mut builder = tar::Builder::new(File::create(dir.join("sync/test.db")).unwrap());
+            let mut header = tar::Header::new_gnu();
+            header.set_size(desc.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, "package-1-1/desc", desc.as_bytes()).unwrap();
+            builder.finish().unwrap();
+
+            let alpm = Alpm::new("/", dir.to_str().unwrap()).unwrap();
+            alpm.register_syncdb("test", SigLevel::NONE).unwrap();
+            alpm
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn satisfies_agrees_with_find_satisfier(provides in spec("provision"), depends in spec("provision")) {
+                let dir = env::temp_dir().join(format!("paketbote-spec-{}", std::process::id()));
+                let alpm = create_alpm(&dir, &provides);
+                let expected = alpm.syncdbs().find_satisfier(depends.as_str()).is_some();
+                let provides: Spec = provides.parse().unwrap();
+                let depends: Spec = depends.parse().unwrap();
+                prop_assert_eq!(provides.satisfies(&depends), expected);
+            }
+        }
+    }
 }
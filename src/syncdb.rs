@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use fehler::throws;
+use libflate::gzip;
+use tar::Archive;
+use xz2::read::XzDecoder;
+
+use crate::config::Upstream;
+use crate::config::UpstreamRepo;
+use crate::error::Error;
+use crate::spec::Spec;
+use crate::spec::Version;
+
+/// A package in a sync database.
+#[derive(Debug, Clone)]
+pub struct SyncPackage {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub provides: Vec<Spec>,
+}
+
+impl SyncPackage {
+    /// Whether the package satisfies the dependency by its own name and
+    /// version.
+    fn satisfies_literally(&self, depends: &Spec) -> bool {
+        Spec::new(self.name.clone(), Version::new_eq(self.version.clone())).satisfies(depends)
+    }
+
+    fn satisfies_by_provision(&self, depends: &Spec) -> bool {
+        self.provides.iter().any(|p| p.satisfies(depends))
+    }
+}
+
+/// The packages of a sync database, like `core.db`.
+#[derive(Debug)]
+pub struct SyncDb {
+    pub name: String,
+    pub packages: Vec<SyncPackage>,
+    by_name: HashMap<String, usize>,
+    by_provision: HashMap<String, Vec<usize>>,
+}
+
+impl SyncDb {
+    pub fn new(name: String, packages: Vec<SyncPackage>) -> SyncDb {
+        let mut by_name = HashMap::new();
+        let mut by_provision = HashMap::new();
+        for (i, package) in packages.iter().enumerate() {
+            by_name.insert(package.name.clone(), i);
+            for provides in &package.provides {
+                by_provision.entry(provides.name.clone()).or_insert_with(Vec::new).push(i);
+            }
+        }
+        SyncDb { name, packages, by_name, by_provision }
+    }
+
+    /// Reads a sync database, which is a tarball (compressed with gzip, xz,
+    /// zstd or not at all) with a directory per package.
+    #[throws]
+    pub fn read(name: &str, path: impl AsRef<Path>) -> SyncDb {
+        let mut reader = BufReader::new(File::open(path)?);
+        let magic = reader.fill_buf()?.to_vec();
+        let reader: Box<dyn Read> = if magic.starts_with(&[0x1f, 0x8b]) {
+            Box::new(gzip::Decoder::new(reader)?)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z']) {
+            Box::new(XzDecoder::new(reader))
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Box::new(zstd::Decoder::new(reader)?)
+        } else {
+            Box::new(reader)
+        };
+
+        // Older databases split the fields of a package into `desc` and
+        // `depends`, so the fields are collected per directory first.
+        let mut fields: HashMap<String, HashMap<String, Vec<String>>> = HashMap::new();
+        let mut archive = Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            let file = path.file_name().and_then(|f| f.to_str());
+            if file != Some("desc") && file != Some("depends") {
+                continue;
+            }
+            if let Some(dir) = path.parent().and_then(|d| d.to_str()) {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                fields.entry(dir.to_owned()).or_insert_with(HashMap::new).extend(parse_desc(&content));
+            }
+        }
+
+        let mut packages = fields.into_iter()
+            .map(|(dir, fields)| -> Result<SyncPackage, Error> {
+                let single = |key: &str| fields.get(key)
+                    .and_then(|values| values.first())
+                    .cloned()
+                    .ok_or_else(|| format!("Field {} is missing for {} in {}", key, dir, name));
+                let provides = fields.get("PROVIDES").map_or(&[][..], |p| p.as_slice()).iter()
+                    .map(|p| p.parse())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(SyncPackage {
+                    name: single("NAME")?,
+                    version: single("VERSION")?,
+                    arch: single("ARCH").unwrap_or_default(),
+                    provides
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        packages.sort_by(|p, q| p.name.cmp(&q.name));
+        SyncDb::new(name.to_owned(), packages)
+    }
+
+    pub fn pkg(&self, name: &str) -> Option<&SyncPackage> {
+        self.by_name.get(name).map(|i| &self.packages[*i])
+    }
+}
+
+/// Parses the `%KEY%` sections of a `desc` file.
+fn parse_desc(content: &str) -> HashMap<String, Vec<String>> {
+    let mut fields = HashMap::new();
+    let mut key: Option<String> = None;
+    for line in content.lines() {
+        if line.len() > 2 && line.starts_with('%') && line.ends_with('%') {
+            key = Some(line[1..line.len() - 1].to_owned());
+        } else if line.is_empty() {
+            key = None;
+        } else if let Some(key) = &key {
+            fields.entry(key.clone()).or_insert_with(Vec::new).push(line.to_owned());
+        }
+    }
+    fields
+}
+
+/// The sync databases of an architecture, in the order in which pacman
+/// would search them.
+#[derive(Debug)]
+pub struct SyncDbs(pub Vec<SyncDb>);
+
+impl SyncDbs {
+    /// Reads the synced databases of the upstream repositories of the
+    /// architecture. Repositories that have not been synced yet are skipped.
+    #[throws]
+    pub fn load(arch: &str, repos: &[UpstreamRepo]) -> SyncDbs {
+        let mut dbs = Vec::new();
+        for repo in repos {
+            let path = sync_dir(arch).join(format!("{}.db", repo.name));
+            if path.exists() {
+                dbs.push(SyncDb::read(&repo.name, &path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?);
+            }
+        }
+        SyncDbs(dbs)
+    }
+
+    /// Finds a package that satisfies the dependency like pacman does: a
+    /// package with the name of the dependency is preferred over packages
+    /// that provide it, and earlier databases are preferred over later ones.
+    pub fn find_satisfier(&self, depends: &str) -> Option<(&SyncDb, &SyncPackage)> {
+        let depends: Spec = depends.parse().ok()?;
+        for db in &self.0 {
+            if let Some(package) = db.pkg(&depends.name) {
+                if package.satisfies_literally(&depends) {
+                    return Some((db, package));
+                }
+            }
+        }
+        for db in &self.0 {
+            let candidates = db.by_provision.get(&depends.name).map_or(&[][..], |c| c.as_slice());
+            for i in candidates {
+                let package = &db.packages[*i];
+                if package.satisfies_by_provision(&depends) {
+                    return Some((db, package));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The directory that the upstream databases of the architecture are synced
+/// into.
+pub fn sync_dir(arch: &str) -> PathBuf {
+    Path::new("pacman-db").join(arch).join("sync")
+}
+
+/// The upstream databases of every architecture. They are read on first use
+/// and kept in memory until they are synced again.
+pub struct UpstreamDbs {
+    upstream: Upstream,
+    loaded: Mutex<HashMap<String, (SyncState, Arc<SyncDbs>)>>,
+}
+
+impl UpstreamDbs {
+    pub fn new(upstream: Upstream) -> UpstreamDbs {
+        UpstreamDbs { upstream, loaded: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn upstream(&self) -> &Upstream {
+        &self.upstream
+    }
+
+    /// Returns the databases of the architecture, reading them again if they
+    /// were synced since they were last read.
+    #[throws]
+    pub fn get(&self, arch: &str) -> Arc<SyncDbs> {
+        let synced = sync_state(arch);
+        let mut loaded = self.loaded.lock().unwrap();
+        if let Some((loaded_synced, dbs)) = loaded.get(arch) {
+            if *loaded_synced == synced {
+                return dbs.clone();
+            }
+        }
+        let dbs = Arc::new(SyncDbs::load(arch, self.upstream.repos(arch))?);
+        loaded.insert(arch.to_owned(), (synced, dbs.clone()));
+        dbs
+    }
+}
+
+/// What the synced databases of an architecture look like on disk: the
+/// directory that `pacman-db/<arch>` links to, and the name, modification time
+/// and size of every database. The modification times are those of the
+/// servers, so they alone don't show whether a sync changed anything, but
+/// every sync swaps the link.
+#[derive(Debug, PartialEq)]
+struct SyncState {
+    target: Option<PathBuf>,
+    files: Vec<(PathBuf, Option<SystemTime>, u64)>,
+}
+
+fn sync_state(arch: &str) -> SyncState {
+    let target = fs::read_link(Path::new("pacman-db").join(arch)).ok();
+    let mut files = fs::read_dir(sync_dir(arch)).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.modified().ok(), metadata.len()))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    SyncState { target, files }
+}
@@ -0,0 +1,190 @@
+use std::cmp::Ordering;
+
+/// Compares two package versions of the form `[epoch:]version[-pkgrel]` like
+/// pacman does. The pkgrel is only compared if both versions have one.
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let (epoch_a, version_a, release_a) = split_evr(a);
+    let (epoch_b, version_b, release_b) = split_evr(b);
+
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(version_a, version_b))
+        .then_with(|| match (release_a, release_b) {
+            (Some(release_a), Some(release_b)) => rpmvercmp(release_a, release_b),
+            _ => Ordering::Equal
+        })
+}
+
+/// Splits a version into epoch, version and pkgrel. The epoch defaults to `0`.
+fn split_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let digits = evr.bytes().take_while(u8::is_ascii_digit).count();
+    let (epoch, rest) = if evr[digits..].starts_with(':') {
+        let epoch = &evr[..digits];
+        (if epoch.is_empty() { "0" } else { epoch }, &evr[digits + 1..])
+    } else {
+        ("0", evr)
+    };
+    match rest.rfind('-') {
+        Some(i) => (epoch, &rest[..i], Some(&rest[i + 1..])),
+        None => (epoch, rest, None)
+    }
+}
+
+/// Compares two version strings segment by segment, where a segment is either
+/// a run of digits or a run of letters. Numeric segments are newer than alpha
+/// segments, and a version with an additional alpha segment is older, so that
+/// `1.0rc1` comes before `1.0`.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        let separator_start = (one, two);
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one == a.len() || two == b.len() {
+            break;
+        }
+
+        // A different number of separators means the segments don't line up.
+        let separators = (one - separator_start.0, two - separator_start.1);
+        if separators.0 != separators.1 {
+            return separators.0.cmp(&separators.1);
+        }
+
+        let is_num = a[one].is_ascii_digit();
+        let segment_end = |s: &[u8], start: usize| {
+            let len = s[start..].iter()
+                .take_while(|c| if is_num { c.is_ascii_digit() } else { c.is_ascii_alphabetic() })
+                .count();
+            start + len
+        };
+        let (end_one, end_two) = (segment_end(a, one), segment_end(b, two));
+
+        if end_two == two {
+            // The segments are of different types, numeric ones are newer.
+            return if is_num { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let mut segment_one = &a[one..end_one];
+        let mut segment_two = &b[two..end_two];
+        if is_num {
+            while segment_one.first() == Some(&b'0') {
+                segment_one = &segment_one[1..];
+            }
+            while segment_two.first() == Some(&b'0') {
+                segment_two = &segment_two[1..];
+            }
+            let ord = segment_one.len().cmp(&segment_two.len());
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        let ord = segment_one.cmp(segment_two);
+        if ord != Ordering::Equal {
+            return ord;
+        }
+
+        one = end_one;
+        two = end_two;
+    }
+
+    let rest_one = &a[one..];
+    let rest_two = &b[two..];
+    if rest_one.is_empty() && rest_two.is_empty() {
+        return Ordering::Equal;
+    }
+
+    // The version with the remaining alpha segment is older, e.g. 1.0a < 1.0,
+    // otherwise the version with something remaining is newer, e.g. 1.0 < 1.0.1.
+    let alpha_one = rest_one.first().map_or(false, u8::is_ascii_alphabetic);
+    let alpha_two = rest_two.first().map_or(false, u8::is_ascii_alphabetic);
+    if (rest_one.is_empty() && !alpha_two) || alpha_one {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+    use std::cmp::Ordering::*;
+
+    use super::vercmp;
+
+    #[test]
+    fn test_vercmp() {
+        fn test(a: &str, b: &str, expected: Ordering) {
+            assert_eq!(vercmp(a, b), expected, "vercmp({}, {})", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "vercmp({}, {})", b, a);
+        }
+
+        test("1.5.0", "1.5.0", Equal);
+        test("1.5.1", "1.5.0", Greater);
+        test("1.5.1", "1.5", Greater);
+        test("1.5.0-1", "1.5.0-2", Less);
+        test("1.5.0-1", "1.5.1-1", Less);
+        test("1.5-2", "1.5.1-1", Less);
+        test("1.5", "1.5-1", Equal);
+        test("1.1-1", "1.1", Equal);
+        test("1.0-1", "1.1", Less);
+        test("1.5b-1", "1.5-1", Less);
+        test("1.5b", "1.5", Less);
+        test("1.5b", "1.5.1", Less);
+        test("1.0a", "1.0alpha", Less);
+        test("1.0alpha", "1.0b", Less);
+        test("1.0b", "1.0beta", Less);
+        test("1.0beta", "1.0rc", Less);
+        test("1.0rc", "1.0", Less);
+        test("1.5.a", "1.5", Greater);
+        test("1.5.b", "1.5.a", Greater);
+        test("1.5.1", "1.5.b", Greater);
+        test("1.5.b-1", "1.5.b", Equal);
+        test("1.5-1", "1.5.b", Less);
+        test("2.0", "2_0", Equal);
+        test("2.0_a", "2_0.a", Equal);
+        test("2.0a", "2.0.a", Less);
+        test("2___a", "2_a", Greater);
+        test("0:1.0", "0:1.0", Equal);
+        test("0:1.0", "0:1.1", Less);
+        test("1:1.0", "0:1.0", Greater);
+        test("1:1.0", "0:1.1", Greater);
+        test("1:1.0", "2:1.1", Less);
+        test("1:1.0", "0:1.0-1", Greater);
+        test("1:1.0-1", "0:1.1-1", Greater);
+        test("0:1.0", "1.0", Equal);
+        test("0:1.0", "1.1", Less);
+        test("1:1.0", "1.1", Greater);
+        test("1.0.010", "1.0.9", Greater);
+    }
+
+    #[cfg(feature = "alpm")]
+    mod alpm {
+        use proptest::prelude::*;
+
+        use super::super::vercmp;
+
+        proptest! {
+            #[test]
+            fn agrees_with_libalpm(
+                a in "([0-9]:)?[0-9a-z._+]{1,8}(-[0-9.]{1,3})?",
+                b in "([0-9]:)?[0-9a-z._+]{1,8}(-[0-9.]{1,3})?"
+            ) {
+                prop_assert_eq!(vercmp(&a, &b), ::alpm::vercmp(&a, &b));
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use std::io::Read;
+use std::net::IpAddr;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use fehler::throws;
+use regex::Regex;
+use reqwest::Url;
+use serde::Deserialize;
+use wait_timeout::ChildExt;
+
+use crate::db::models::VersionSource;
+use crate::db::models::VersionSourceKind;
+use crate::error::Error;
+use crate::net::check_public_url;
+use crate::net::get_public;
+use crate::vercmp::vercmp;
+
+/// How long requests, git and commands may take before they are given up.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// Looks up the latest version of the package at its version source.
+///
+/// Every kind of source yields a number of candidates: release tags, git tags,
+/// the text of a web page or the lines that a command prints. If the source
+/// has a pattern, the versions are its matches within the candidates (the
+/// first capture group, if there is one); otherwise the candidates are the
+/// versions, with a leading `v` removed. The newest of them according to `vercmp` wins.
+#[throws]
+pub fn latest_version(source: &VersionSource) -> String {
+    let candidates = match source.kind {
+        VersionSourceKind::GitHub => github_releases(&source.source)?,
+        VersionSourceKind::GitLab => gitlab_releases(&source.source)?,
+        VersionSourceKind::Git => git_tags(&source.source)?,
+        VersionSourceKind::Regex => {
+            if source.pattern.is_none() {
+                Err("A regex version source needs a pattern")?
+            }
+            let url = parse_url(&source.source)?;
+            vec![get_public(url, TIMEOUT)?.error_for_status()?.text()?]
+        }
+        VersionSourceKind::Command => command_output(&source.source)?.lines()
+            .map(|line| line.to_owned())
+            .collect()
+    };
+
+    let pattern = source.pattern.as_ref()
+        .map(|p| Regex::new(p).map_err(|e| format!("Invalid pattern {}: {}", p, e)))
+        .transpose()?;
+    let versions = match &pattern {
+        Some(pattern) => candidates.iter()
+            .flat_map(|c| pattern.captures_iter(c)
+                .map(|m| m.get(1).or_else(|| m.get(0)).unwrap().as_str().to_owned())
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        None => candidates.iter()
+            .map(|c| strip_v(c.trim()).to_owned())
+            .filter(|c| !c.is_empty())
+            .collect()
+    };
+
+    versions.into_iter()
+        .max_by(|a, b| vercmp(a, b))
+        .ok_or_else(|| format!("No version found at {}", source.source))?
+}
+
+/// The URL at which people can see the releases of the source, if there is
+/// one. Only HTTP(S) URLs are returned, since they end up in links.
+pub fn source_url(kind: VersionSourceKind, source: &str) -> Option<String> {
+    let url = match kind {
+        VersionSourceKind::GitHub => format!("https://github.com/{}/releases", source),
+        VersionSourceKind::GitLab => format!("{}/-/releases", source.trim_end_matches('/')),
+        VersionSourceKind::Git | VersionSourceKind::Regex => source.to_owned(),
+        VersionSourceKind::Command => return None
+    };
+    Url::parse(&url).ok()
+        .filter(|url| (url.scheme() == "http" || url.scheme() == "https") && url.host().is_some())
+        .map(|url| url.to_string())
+}
+
+/// Whether the source is an HTTPS URL with a host, as GitLab and git sources
+/// have to be.
+pub fn is_https_url(source: &str) -> bool {
+    Url::parse(source).map_or(false, |url| url.scheme() == "https" && url.host().is_some())
+}
+
+/// Turns `v1.2.3` into `1.2.3`, but leaves names like `vim` alone.
+fn strip_v(tag: &str) -> &str {
+    let mut chars = tag.chars();
+    match (chars.next(), chars.next()) {
+        (Some('v'), Some(c)) | (Some('V'), Some(c)) if c.is_ascii_digit() => &tag[1..],
+        _ => tag
+    }
+}
+
+#[throws]
+fn http_client() -> reqwest::blocking::Client {
+    // GitHub rejects requests without a user agent.
+    reqwest::blocking::Client::builder()
+        .user_agent("paketbote")
+        .timeout(TIMEOUT)
+        .build()?
+}
+
+#[throws]
+fn parse_url(source: &str) -> Url {
+    Url::parse(source)
+        .map_err(|e| format!("Invalid URL {}: {}", source, e))?
+}
+
+/// The tags of the published releases of a GitHub repository, newest first.
+#[throws]
+fn github_releases(repository: &str) -> Vec<String> {
+    let url = format!("https://api.github.com/repos/{}/releases", repository);
+    let releases: Vec<Release> = http_client()?.get(&url).send()?.error_for_status()?.json()?;
+    releases.into_iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .map(|r| r.tag_name)
+        .collect()
+}
+
+/// The tags of the releases of a GitLab project, newest first.
+#[throws]
+fn gitlab_releases(project_url: &str) -> Vec<String> {
+    let mut url = parse_url(project_url.trim_end_matches('/'))?;
+    let project = url.path().trim_start_matches('/').replace('/', "%2F");
+    url.set_path(&format!("/api/v4/projects/{}/releases", project));
+    let releases: Vec<Release> = get_public(url, TIMEOUT)?.error_for_status()?.json()?;
+    releases.into_iter()
+        .filter(|r| !r.prerelease)
+        .map(|r| r.tag_name)
+        .collect()
+}
+
+/// The tags of a git repository, which has to be served over HTTPS. Git is
+/// kept from using any other protocol, from following redirects and from
+/// resolving the host again, since these would get around the check of the
+/// address.
+#[throws]
+fn git_tags(repository: &str) -> Vec<String> {
+    let url = parse_url(repository)?;
+    if url.scheme() != "https" {
+        Err(format!("{} is not an HTTPS URL", repository))?
+    }
+    let addr = check_public_url(&url)?;
+    let mut git = Command::new("git");
+    if let Some(domain) = url.domain() {
+        let ip = match addr.ip() {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip)
+        };
+        let port = url.port_or_known_default().unwrap_or(443);
+        git.arg("-c").arg(format!("http.curloptResolve={}:{}:{}", domain, port, ip));
+    }
+    let output = command_output_of(git
+        .args(&["-c", "protocol.allow=never", "-c", "protocol.https.allow=always"])
+        .args(&["-c", "http.followRedirects=false"])
+        .args(&["ls-remote", "--tags", "--refs", "--", url.as_str()])
+        .env("GIT_PROTOCOL_FROM_USER", "0")
+        .env("GIT_TERMINAL_PROMPT", "0"))?;
+    output.lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .map(|r| r.trim_start_matches("refs/tags/").to_owned())
+        .collect()
+}
+
+#[throws]
+fn command_output(command: &str) -> String {
+    command_output_of(Command::new("sh").args(&["-c", command]))?
+}
+
+/// Runs the command and returns what it prints. The command is killed if it
+/// doesn't finish within `TIMEOUT`.
+#[throws]
+fn command_output_of(command: &mut Command) -> String {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Read the pipes while waiting, so the child can't block on a full one.
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let status = match child.wait_timeout(TIMEOUT)? {
+        Some(status) => status,
+        None => {
+            child.kill()?;
+            child.wait()?;
+            Err(format!("{:?} did not finish within {} seconds", command, TIMEOUT.as_secs()))?
+        }
+    };
+
+    let stdout = stdout.join().map_err(|_| "Failed to read output")??;
+    let stderr = stderr.join().map_err(|_| "Failed to read output")??;
+    if !status.success() {
+        Err(format!("{:?} failed with {}: {}",
+            command, status, String::from_utf8_lossy(&stderr).trim()))?
+    }
+    String::from_utf8(stdout)?
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buffer)?;
+        }
+        Ok(buffer)
+    })
+}
+
+#[test]
+fn test_strip_v() {
+    assert_eq!(strip_v("v1.2.3"), "1.2.3");
+    assert_eq!(strip_v("V2"), "2");
+    assert_eq!(strip_v("1.2.3"), "1.2.3");
+    assert_eq!(strip_v("vim-8.2"), "vim-8.2");
+    assert_eq!(strip_v("v"), "v");
+}
+
+#[test]
+fn test_git_tags_needs_https() {
+    assert!(git_tags("ext::sh -c touch% /tmp/pwned").is_err());
+    assert!(git_tags("--upload-pack=touch /tmp/pwned").is_err());
+    assert!(git_tags("file:///etc").is_err());
+    assert!(git_tags("http://127.0.0.1/repo.git").is_err());
+}
+
+#[test]
+fn test_source_url() {
+    assert_eq!(source_url(VersionSourceKind::GitHub, "owner/repo").as_deref(),
+        Some("https://github.com/owner/repo/releases"));
+    assert_eq!(source_url(VersionSourceKind::GitLab, "https://gitlab.com/group/project/").as_deref(),
+        Some("https://gitlab.com/group/project/-/releases"));
+    assert_eq!(source_url(VersionSourceKind::Regex, "http://example.com/downloads").as_deref(),
+        Some("http://example.com/downloads"));
+    assert_eq!(source_url(VersionSourceKind::GitLab, "javascript:alert(document.cookie)//"), None);
+    assert_eq!(source_url(VersionSourceKind::Git, "javascript:alert(1)"), None);
+    assert_eq!(source_url(VersionSourceKind::Regex, "data:text/html,<script>"), None);
+    assert_eq!(source_url(VersionSourceKind::Command, "curl https://example.com"), None);
+    assert!(is_https_url("https://gitlab.com/group/project"));
+    assert!(!is_https_url("http://gitlab.com/group/project"));
+    assert!(!is_https_url("javascript:alert(1)//"));
+    assert!(!is_https_url("https://"));
+}
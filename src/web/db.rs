@@ -1,2 +1,12 @@
+use rocket_contrib::databases::r2d2;
+
 #[database("main")]
 pub struct Db(diesel::PgConnection);
+
+impl DbPool {
+    /// Takes a connection from the pool. Requests that wait for something use
+    /// this to hold a connection only while they need it.
+    pub fn get(&self) -> Result<Db, r2d2::Error> {
+        self.0.get().map(Db)
+    }
+}
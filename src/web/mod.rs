@@ -2,6 +2,8 @@ use std::backtrace::Backtrace;
 use std::error::Error as StdError;
 use std::io::Cursor;
 
+use rocket::fairing::AdHoc;
+use rocket::http::ContentType;
 use rocket::http::Status;
 use rocket::Request;
 use rocket::Response;
@@ -12,6 +14,9 @@ use thiserror::Error;
 
 use db::Db;
 
+use crate::config::Settings;
+use crate::syncdb::UpstreamDbs;
+
 mod models;
 mod ctx_base;
 mod referer;
@@ -22,9 +27,25 @@ mod principal;
 mod routes;
 
 #[catch(401)]
-fn catch_401_unauthorized() -> String {
-    "Please provide a login cookie or access token.\n".into()
+fn catch_401_unauthorized() -> Error {
+    Error::Unauthorized
+}
+
+#[catch(403)]
+fn catch_403_forbidden() -> Error {
+    Error::Forbidden
+}
+
+#[catch(404)]
+fn catch_404_not_found() -> Error {
+    Error::NotFound
 }
+
+#[catch(422)]
+fn catch_422_unprocessable_entity() -> Error {
+    Error::BadRequest("The request body is not valid.".into())
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Not found")]
@@ -33,6 +54,9 @@ pub enum Error {
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("Forbidden")]
+    Forbidden,
+
     #[error("Conflict")]
     Conflict,
 
@@ -58,6 +82,8 @@ macro_rules! web_error_from {
 web_error_from!(crate::error::Error);
 web_error_from!(std::io::Error);
 web_error_from!(diesel::result::Error);
+web_error_from!(serde_json::error::Error);
+web_error_from!(rocket_contrib::databases::r2d2::Error);
 
 impl Into<Status> for &Error {
     fn into(self) -> Status {
@@ -66,14 +92,32 @@ impl Into<Status> for &Error {
             NotFound => Status::NotFound,
             Conflict => Status::Conflict,
             Unauthorized => Status::Unauthorized,
+            Forbidden => Status::Forbidden,
             BadRequest(_) => Status::BadRequest,
             InternalServerError(_, _) => Status::InternalServerError,
         }
     }
 }
 
+impl Error {
+    /// Identifies the error in the responses of the API.
+    fn code(&self) -> &'static str {
+        use Error::*;
+        match self {
+            NotFound => "not_found",
+            Unauthorized => "unauthorized",
+            Forbidden => "forbidden",
+            Conflict => "conflict",
+            BadRequest(_) => "bad_request",
+            InternalServerError(_, _) => "internal_server_error"
+        }
+    }
+}
+
+/// Errors are described by text, except for requests to the API, which get a
+/// JSON object with the `error` code and a `message`.
 impl<'r> Responder<'r> for Error {
-    fn respond_to(self, _: &Request) -> rocket::response::Result<'r> {
+    fn respond_to(self, request: &Request) -> rocket::response::Result<'r> {
         use Error::*;
         let status = (&self).into();
         let body = match &self {
@@ -90,12 +134,20 @@ impl<'r> Responder<'r> for Error {
                 "Not Found.".into(),
             Unauthorized =>
                 "Please provide a login cookie or access token.".into(),
+            Forbidden =>
+                "You are not allowed to do this.".into(),
             Conflict =>
                 "Cannot create resource because of a conflict.".into()
         };
-        let body = format!("{}\n", body);
+        let (content_type, body) = if request.uri().path().starts_with("/api/") {
+            let body = serde_json::json!({ "error": self.code(), "message": body });
+            (ContentType::JSON, body.to_string())
+        } else {
+            (ContentType::Plain, format!("{}\n", body))
+        };
         Response::build()
             .status(status)
+            .header(content_type)
             .sized_body(Cursor::new(body))
             .ok()
     }
@@ -105,8 +157,22 @@ pub fn run() {
     rocket::ignite()
         .attach(Db::fairing())
         .attach(Template::fairing())
+        .attach(AdHoc::on_attach("Settings", |rocket| {
+            match Settings::from_config(rocket.config()) {
+                Ok(settings) => Ok(rocket
+                    .manage(UpstreamDbs::new(settings.upstream.clone()))
+                    .manage(settings)),
+                Err(error) => {
+                    println!("{}", error);
+                    Err(rocket)
+                }
+            }
+        }))
         .register(catchers![
-            catch_401_unauthorized])
+            catch_401_unauthorized,
+            catch_403_forbidden,
+            catch_404_not_found,
+            catch_422_unprocessable_entity])
         .mount("/public",
             StaticFiles::from("public").rank(-100))
         .mount("/", routes![
@@ -120,15 +186,64 @@ pub fn run() {
             routes::access_tokens::route_access_tokens,
             routes::access_tokens::route_access_tokens_create,
             routes::access_tokens::route_access_tokens_delete,
+            routes::notifications::route_notifications,
+            routes::notifications::route_notifications_set,
+            routes::notifications::route_notifications_confirm,
+            routes::notifications::route_notifications_delete,
             routes::repo::route_repo_text,
             routes::repo::route_repo_html,
             routes::repo::route_repo_create,
             routes::repo::route_delete_obsolete,
+            routes::repo::route_layer_add,
+            routes::repo::route_layer_remove,
+            routes::webhooks::route_webhooks,
+            routes::webhooks::route_webhook_add,
+            routes::webhooks::route_webhook_remove,
+            routes::feed::route_repo_feed,
+            routes::feed::route_account_feed,
+            routes::graph::route_graph_json,
+            routes::graph::route_graph_dot,
+            routes::graph::route_graph_html,
             routes::package::route_get_package,
             routes::package::route_activate_package,
+            routes::package::route_version_source_set,
+            routes::package::route_version_source_remove,
+            routes::package::route_package_json,
+            routes::package::route_package_html,
             routes::upload::upload,
+            routes::remove::route_remove_preview_json,
+            routes::remove::route_remove_preview_html,
             routes::remove::route_remove,
             routes::search::route_search,
-            routes::search::route_search_results])
+            routes::search::route_search_results,
+            routes::jobs::route_jobs_json,
+            routes::jobs::route_jobs_html,
+            routes::jobs::route_job_status,
+            routes::jobs::route_job_retry,
+            routes::jobs::route_job_cancel,
+            routes::admin::route_admin])
+        .mount("/api/v1", routes![
+            routes::api::accounts::route_active_account,
+            routes::api::accounts::route_account,
+            routes::api::repos::route_repos,
+            routes::api::repos::route_repo_create,
+            routes::api::repos::route_repo,
+            routes::api::repos::route_repo_delete,
+            routes::api::repos::route_missing_deps,
+            routes::api::repos::route_history,
+            routes::api::packages::route_packages,
+            routes::api::packages::route_package,
+            routes::api::packages::route_package_upload,
+            routes::api::packages::route_package_activate,
+            routes::api::packages::route_package_delete,
+            routes::api::packages::route_delete_obsolete,
+            routes::api::search::route_search,
+            routes::api::tokens::route_tokens,
+            routes::api::tokens::route_token_create,
+            routes::api::tokens::route_token_delete,
+            routes::api::jobs::route_jobs,
+            routes::api::jobs::route_job,
+            routes::api::jobs::route_job_retry,
+            routes::api::jobs::route_job_cancel])
         .launch();
 }
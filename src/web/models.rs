@@ -1,16 +1,30 @@
 use std::cmp::Ordering;
 
-use alpm::vercmp;
 use chrono::DateTime;
+use chrono::NaiveDateTime;
 use chrono::Utc;
 use diesel::PgConnection;
 use fehler::throws;
+use itertools::Itertools;
 use serde::Serialize;
 
+use crate::config::Aur;
 use crate::db;
 use crate::db::get_aur_version;
+use crate::db::get_install_problems_by_package;
+use crate::db::get_jobs_by_package;
+use crate::db::get_missing_deps_by_package;
+use crate::db::get_needed_rebuilds_by_package;
+use crate::db::get_upstream_collisions_by_package;
+use crate::db::get_version_source;
+use crate::db::models::DependencyKind;
+use crate::db::models::JobStatus;
+use crate::db::models::MissingDep;
 use crate::error::Error;
 use crate::format_pkg_filename;
+use crate::jobs::describe_job;
+use crate::vercmp::vercmp;
+use crate::version_source::source_url;
 
 #[derive(Debug, Serialize)]
 pub struct Package {
@@ -24,18 +38,89 @@ pub struct Package {
     pub created: String,
     pub active: bool,
     pub aur_package: Option<AurPackage>,
+    pub upstream_version: Option<UpstreamVersion>,
+    pub actions: Vec<PackageAction>,
+    /// The missing runtime dependencies.
+    pub missing_deps: Vec<String>,
+    pub missing_optdepends: Vec<String>,
+    pub missing_makedepends: Vec<String>,
+    pub missing_checkdepends: Vec<String>,
+    pub install_problems: Vec<InstallProblem>,
+    pub needed_rebuilds: Vec<NeededRebuild>,
+    pub upstream_collisions: Vec<UpstreamCollision>,
     pub repo_id: i32
 }
 
+#[derive(Debug, Serialize)]
+pub struct InstallProblem {
+    pub arch: String,
+    pub kind: String,
+    pub message: String
+}
+
+#[derive(Debug, Serialize)]
+pub struct NeededRebuild {
+    pub arch: String,
+    pub dependency: String,
+    pub available: String
+}
+
+/// A package of the same name in an upstream repository.
+#[derive(Debug, Serialize)]
+pub struct UpstreamCollision {
+    pub arch: String,
+    pub repo: String,
+    pub version: String,
+    /// Whether our version is `older`, `newer` or the `same`.
+    pub status: String
+}
+
+#[throws]
+pub fn augment_package(conn: &PgConnection, aur: &Aur, package: db::models::Package) -> Package {
+    let missing = get_missing_deps_by_package(conn, package.id)?;
+    augment_package_with_missing(conn, aur, package, &missing)?
+}
+
+/// Like `augment_package`, but takes the missing dependencies from `missing`,
+/// so that they can be loaded for a whole repository at once.
 #[throws]
-pub fn augment_package(conn: &PgConnection, package: db::models::Package) -> Package {
+pub fn augment_package_with_missing(conn: &PgConnection, aur: &Aur, package: db::models::Package, missing: &[MissingDep]) -> Package {
     let created = DateTime::<Utc>::from_utc(package.created, Utc);
     let created_fmt = created
         .format("%Y-%m-%d")
         .to_string();
     let archive_file = format_pkg_filename(&package);
     let signature_file = format!("{}.sig", archive_file);
-    let aur_package = load_aur_package(conn, &package)?;
+    let aur_package = load_aur_package(conn, aur, &package)?;
+    let upstream_version = load_upstream_version(conn, &package)?;
+    let actions = load_package_actions(conn, &package)?;
+    let package_id = package.id;
+    let missing_of = |kind: DependencyKind| missing.iter()
+        .filter(|m| m.package_id == package_id && m.kind == kind)
+        .map(|m| m.dependency.clone())
+        .unique()
+        .collect::<Vec<_>>();
+    let install_problems = get_install_problems_by_package(conn, package.id)?.into_iter()
+        .map(|p| InstallProblem { arch: p.arch, kind: p.kind, message: p.message })
+        .collect();
+    let needed_rebuilds = get_needed_rebuilds_by_package(conn, package.id)?.into_iter()
+        .map(|r| NeededRebuild { arch: r.arch, dependency: r.dependency, available: r.available })
+        .collect();
+    let upstream_collisions = get_upstream_collisions_by_package(conn, package.id)?.into_iter()
+        .map(|c| {
+            let status = match vercmp(&package.version, &c.upstream_version) {
+                Ordering::Less => "older",
+                Ordering::Equal => "same",
+                Ordering::Greater => "newer"
+            };
+            UpstreamCollision {
+                arch: c.arch,
+                repo: c.upstream_repo,
+                version: c.upstream_version,
+                status: status.to_owned()
+            }
+        })
+        .collect();
     Package {
         id: package.id,
         name: package.name,
@@ -47,33 +132,158 @@ pub fn augment_package(conn: &PgConnection, package: db::models::Package) -> Pac
         created: created_fmt,
         active: package.active,
         aur_package: aur_package,
+        upstream_version,
+        actions: actions,
+        missing_deps: missing_of(DependencyKind::Depend),
+        missing_optdepends: missing_of(DependencyKind::OptDepend),
+        missing_makedepends: missing_of(DependencyKind::MakeDepend),
+        missing_checkdepends: missing_of(DependencyKind::CheckDepend),
+        install_problems,
+        needed_rebuilds,
+        upstream_collisions,
         repo_id: package.repo_id,
     }
 }
 
 #[throws]
-fn load_aur_package(conn: &PgConnection, package: &db::models::Package) -> Option<AurPackage> {
-    if let Some(version) = get_aur_version(conn, &package.name)? {
-        let is_newer = vercmp(&version, &package.version) == Ordering::Greater;
-        Some(AurPackage::new(&package.name, &version, is_newer))
+fn load_aur_package(conn: &PgConnection, aur: &Aur, package: &db::models::Package) -> Option<AurPackage> {
+    if let Some(aur_version) = get_aur_version(conn, &package.name)? {
+        let is_newer = vercmp(&aur_version.version, &package.version) == Ordering::Greater;
+        let url = aur.package_url(&aur_version.package);
+        Some(AurPackage::new(aur_version, is_newer, url))
     } else {
         None
     }
 }
 
+#[throws]
+fn load_upstream_version(conn: &PgConnection, package: &db::models::Package) -> Option<UpstreamVersion> {
+    get_version_source(conn, package.repo_id, &package.name)?
+        .map(|source| UpstreamVersion::new(source, &package.version))
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageAction {
+    job_id: i32,
+    operation: String,
+    status: JobStatus,
+    error: Option<String>
+}
+
+#[throws]
+fn load_package_actions(conn: &PgConnection, package: &db::models::Package) -> Vec<PackageAction> {
+    get_jobs_by_package(conn, package.id)?.into_iter()
+        .map(|job| PackageAction {
+            job_id: job.id,
+            operation: describe_job(&job),
+            status: job.status,
+            error: job.error
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct Job {
+    pub id: i32,
+    pub description: String,
+    pub status: JobStatus,
+    pub error: Option<String>,
+    pub created: String,
+    pub updated: String,
+    pub repo: Option<String>,
+    pub package: Option<String>
+}
+
+#[throws]
+pub fn augment_job(conn: &PgConnection, job: db::models::Job) -> Job {
+    let repo = match job.repo_id {
+        Some(repo_id) => Some(db::get_repo(conn, repo_id)?.name),
+        None => None
+    };
+    let package = match job.package_id {
+        Some(package_id) => Some(format_pkg_filename(&db::get_package(conn, package_id)?)),
+        None => None
+    };
+    Job {
+        id: job.id,
+        description: describe_job(&job),
+        status: job.status,
+        error: job.error,
+        created: format_timestamp(job.created),
+        updated: format_timestamp(job.updated),
+        repo,
+        package
+    }
+}
+
+pub fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    DateTime::<Utc>::from_utc(timestamp, Utc)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
 #[derive(Debug, Serialize)]
 pub struct AurPackage {
     name: String,
     version: String,
     is_newer: bool,
-    url: String
+    url: String,
+    maintainer: Option<String>,
+    out_of_date: Option<String>,
+    last_modified: Option<String>,
+    last_checked: String
 }
 
 impl AurPackage {
-    fn new(name: &str, version: &str, is_newer: bool) -> AurPackage {
-        let name = name.to_owned();
-        let version = version.to_owned();
-        let url = format!("https://aur.archlinux.org/packages/{}", name);
-        AurPackage { name, version, is_newer, url }
+    fn new(aur_version: db::models::AurVersion, is_newer: bool, url: String) -> AurPackage {
+        AurPackage {
+            name: aur_version.package,
+            version: aur_version.version,
+            is_newer,
+            url,
+            maintainer: aur_version.maintainer,
+            out_of_date: aur_version.out_of_date.map(format_timestamp),
+            last_modified: aur_version.last_modified.map(format_timestamp),
+            last_checked: format_timestamp(aur_version.last_checked)
+        }
+    }
+}
+
+/// The latest version of a package at its version source.
+#[derive(Debug, Serialize)]
+pub struct UpstreamVersion {
+    kind: String,
+    source: String,
+    pattern: Option<String>,
+    version: Option<String>,
+    is_newer: bool,
+    url: Option<String>,
+    error: Option<String>,
+    last_checked: Option<String>
+}
+
+impl UpstreamVersion {
+    fn new(source: db::models::VersionSource, package_version: &str) -> UpstreamVersion {
+        // Upstream versions have neither an epoch nor a pkgrel.
+        let package_version = strip_epoch(package_version);
+        let is_newer = source.version.as_ref()
+            .map_or(false, |v| vercmp(v, package_version) == Ordering::Greater);
+        UpstreamVersion {
+            kind: source.kind.to_string(),
+            url: source_url(source.kind, &source.source),
+            source: source.source,
+            pattern: source.pattern,
+            version: source.version,
+            is_newer,
+            error: source.error,
+            last_checked: source.last_checked.map(format_timestamp)
+        }
+    }
+}
+
+fn strip_epoch(version: &str) -> &str {
+    match version.find(':') {
+        Some(i) => &version[i + 1..],
+        None => version
     }
 }
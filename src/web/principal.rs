@@ -2,7 +2,9 @@ use fehler::throws;
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome};
 use rocket::Request;
+use rocket::State;
 
+use crate::config::Settings;
 use crate::db::{get_account_by_name, get_account_for_token};
 use crate::db::models::Account;
 use crate::error::Error;
@@ -44,6 +46,29 @@ impl FromRequest<'_, '_> for Account {
     }
 }
 
+/// An account that is listed as an administrator in the settings.
+pub struct Admin(pub Account);
+
+impl FromRequest<'_, '_> for Admin {
+    type Error = Option<Error>;
+    fn from_request(request: &Request) -> Outcome<Self, Self::Error> {
+        let account = match Account::from_request(request) {
+            Outcome::Success(account) => account,
+            Outcome::Failure(failure) => return Outcome::Failure(failure),
+            Outcome::Forward(forward) => return Outcome::Forward(forward)
+        };
+        let settings = match request.guard::<State<Settings>>() {
+            Outcome::Success(settings) => settings,
+            _ => return Outcome::Failure((Status::InternalServerError, None))
+        };
+        if settings.is_admin(&account.name) {
+            Outcome::Success(Admin(account))
+        } else {
+            Outcome::Failure((Status::Forbidden, None))
+        }
+    }
+}
+
 #[throws]
 fn from_request_fallible(request: &Request) -> Option<Account> {
     if let Some(account) = from_authorization_header(request)? {
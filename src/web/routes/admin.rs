@@ -0,0 +1,47 @@
+use fehler::throws;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
+
+use crate::db::get_periodic_tasks;
+use crate::db::get_system_jobs;
+use crate::web::ctx_base::BaseContext;
+use crate::web::Error;
+use crate::web::models::augment_job;
+use crate::web::models::format_timestamp;
+use crate::web::models::Job;
+use crate::web::principal::Admin;
+use crate::web::props::Props;
+
+#[derive(Serialize)]
+struct AdminContext {
+    base: BaseContext,
+    tasks: Vec<PeriodicTask>,
+    jobs: Vec<Job>
+}
+
+#[derive(Serialize)]
+struct PeriodicTask {
+    name: String,
+    period: i32,
+    last_run: Option<String>,
+    next_run: String
+}
+
+#[throws]
+#[get("/admin")]
+pub fn route_admin(props: Props, _admin: Admin) -> Template {
+    let tasks = get_periodic_tasks(&*props.db)?.into_iter()
+        .map(|task| PeriodicTask {
+            name: task.name,
+            period: task.period,
+            last_run: task.last_run.map(format_timestamp),
+            next_run: format_timestamp(task.next_run)
+        })
+        .collect();
+    let jobs = get_system_jobs(&*props.db)?.into_iter()
+        .map(|job| augment_job(&*props.db, job))
+        .collect::<Result<Vec<_>, _>>()?;
+    let base = BaseContext::new(&props.account);
+    let context = AdminContext { base, tasks, jobs };
+    Template::render("admin", context)
+}
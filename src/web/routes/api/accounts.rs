@@ -0,0 +1,36 @@
+use diesel::PgConnection;
+use fehler::throws;
+use rocket_contrib::json::Json;
+use serde::Serialize;
+
+use crate::db::get_repos_by_account;
+use crate::db::models::Account;
+use crate::web::db::Db;
+use crate::web::Error;
+use crate::web::routes::load_account;
+
+#[derive(Serialize)]
+pub struct AccountInfo {
+    name: String,
+    repos: usize
+}
+
+#[throws]
+fn account_info(db: &PgConnection, account: Account) -> AccountInfo {
+    let repos = get_repos_by_account(db, account.id)?.len();
+    AccountInfo { name: account.name, repos }
+}
+
+/// The account that the request is authenticated as.
+#[throws]
+#[get("/account")]
+pub fn route_active_account(db: Db, account: Account) -> Json<AccountInfo> {
+    Json(account_info(&*db, account)?)
+}
+
+#[throws]
+#[get("/accounts/<account>", rank = 1)]
+pub fn route_account(db: Db, account: String) -> Json<AccountInfo> {
+    let account = load_account(&*db, &account)?;
+    Json(account_info(&*db, account)?)
+}
@@ -0,0 +1,57 @@
+use fehler::throws;
+use rocket::response::status::NoContent;
+use rocket::State;
+use rocket_contrib::json::Json;
+
+use crate::db::get_jobs_by_account;
+use crate::db::models::Account;
+use crate::db::Paginated;
+use crate::web::db::Db;
+use crate::web::db::DbPool;
+use crate::web::Error;
+use crate::web::Error::*;
+use crate::web::models::augment_job;
+use crate::web::models::Job;
+use crate::web::routes::api::PAGE_SIZE;
+use crate::web::routes::jobs::cancel_job;
+use crate::web::routes::jobs::load_job;
+use crate::web::routes::jobs::retry_failed_job;
+use crate::web::routes::jobs::wait_for_job_limited;
+
+/// The jobs of the repositories of the account that have not finished yet,
+/// newest first.
+#[throws]
+#[get("/jobs?<page>")]
+pub fn route_jobs(db: Db, account: Account, page: Option<usize>) -> Json<Paginated<Job>> {
+    let jobs = get_jobs_by_account(&*db, account.id)?;
+    let jobs = Paginated::slice(jobs, page.unwrap_or(0), PAGE_SIZE)
+        .try_map(|job| augment_job(&*db, job))?;
+    Json(jobs)
+}
+
+/// Like `GET /jobs/<id>`, the request can wait for the job with `wait`.
+#[throws]
+#[get("/jobs/<id>?<wait>")]
+pub fn route_job(db: Db, pool: State<DbPool>, account: Account, id: i32, wait: Option<u64>) -> Json<Job> {
+    let job = load_job(&*db, &account, id)?;
+    drop(db);
+    let job = wait_for_job_limited(&pool, job.id, wait)?.ok_or(NotFound)?;
+    Json(augment_job(&*pool.get()?, job)?)
+}
+
+#[throws]
+#[post("/jobs/<id>/retry")]
+pub fn route_job_retry(db: Db, account: Account, id: i32) -> Json<Job> {
+    let job = load_job(&*db, &account, id)?;
+    retry_failed_job(&*db, &job)?;
+    let job = load_job(&*db, &account, id)?;
+    Json(augment_job(&*db, job)?)
+}
+
+#[throws]
+#[delete("/jobs/<id>", rank = 1)]
+pub fn route_job_cancel(db: Db, account: Account, id: i32) -> NoContent {
+    let job = load_job(&*db, &account, id)?;
+    cancel_job(&*db, &job)?;
+    NoContent
+}
@@ -0,0 +1,12 @@
+//! The JSON API, mounted at `/api/v1`. It offers what the web interface does,
+//! with lists split into pages and errors described by JSON objects (see the
+//! `Responder` of `web::Error`).
+
+pub mod accounts;
+pub mod repos;
+pub mod packages;
+pub mod search;
+pub mod tokens;
+pub mod jobs;
+
+const PAGE_SIZE: usize = 100;
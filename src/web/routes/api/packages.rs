@@ -0,0 +1,148 @@
+use diesel::PgConnection;
+use fehler::throws;
+use rocket::Data;
+use rocket::State;
+use rocket_contrib::json::Json;
+
+use crate::config::Settings;
+use crate::db::models;
+use crate::db::models::Account;
+use crate::db::models::Repo;
+use crate::db::Paginated;
+use crate::jobs::create_repo_action;
+use crate::jobs::RepoActionOp;
+use crate::syncdb::UpstreamDbs;
+use crate::web::boundary::Boundary;
+use crate::web::db::Db;
+use crate::web::db::DbPool;
+use crate::web::Error;
+use crate::web::models::augment_job;
+use crate::web::models::Job;
+use crate::web::models::Package;
+use crate::web::routes::load_package;
+use crate::web::routes::load_repo;
+use crate::web::routes::package::load_package_details;
+use crate::web::routes::package::PackageDetails;
+use crate::web::routes::remove::preview_removal;
+use crate::web::routes::remove::RemovalPreview;
+use crate::web::routes::remove::schedule_removal;
+use crate::web::routes::repo::get_packages;
+use crate::web::routes::repo::load_obsolete;
+use crate::web::routes::upload::upload_package;
+use crate::web::routes::upload::UploadResponse;
+use crate::web::routes::validate_access;
+
+/// Like `remove::Removal`, a removal that would break other packages is
+/// refused unless it is forced, and the refusal shows what would break.
+#[derive(Responder)]
+pub enum Removal {
+    Removed(Json<Vec<Job>>),
+    #[response(status = 409)]
+    Refused(Json<RemovalPreview>),
+}
+
+#[throws]
+#[get("/repos/<account>/<repo>/packages?<page>")]
+pub fn route_packages(db: Db, settings: State<Settings>, account: String, repo: String, page: Option<usize>) -> Json<Paginated<Package>> {
+    let (_, _, packages) = get_packages(&*db, &settings.aur, &account, &repo, page.unwrap_or(0))?;
+    Json(packages)
+}
+
+#[throws]
+#[get("/repos/<account>/<repo>/packages/<package>")]
+pub fn route_package(db: Db, settings: State<Settings>, account: String, repo: String, package: String) -> Json<PackageDetails> {
+    Json(load_package_details(&*db, &settings.aur, &account, &repo, &package)?)
+}
+
+/// Uploads a package like `POST /<account>/<repo>/<package>` does.
+#[throws]
+#[post("/repos/<account>/<repo>/packages/<package>?<wait>", data = "<data>")]
+pub fn route_package_upload(
+    db: Db,
+    pool: State<DbPool>,
+    active_account: Account,
+    account: String,
+    repo: String,
+    package: String,
+    wait: Option<u64>,
+    boundary: Boundary,
+    data: Data
+) -> Json<UploadResponse>
+{
+    let account = validate_access(active_account, account)?;
+    Json(upload_package(db, &pool, &account, &repo, &package, wait, &boundary.0, data)?)
+}
+
+#[throws]
+#[post("/repos/<account>/<repo>/packages/<package>/activate")]
+pub fn route_package_activate(
+    db: Db,
+    active_account: Account,
+    account: String,
+    repo: String,
+    package: String
+) -> Json<Job>
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let package = load_package(&*db, repo.id, &package)?;
+    let job = create_repo_action(&*db, package.id, RepoActionOp::Add)?;
+    Json(augment_job(&*db, job)?)
+}
+
+#[throws]
+#[delete("/repos/<account>/<repo>/packages/<package>?<force>")]
+pub fn route_package_delete(
+    db: Db,
+    upstream: State<UpstreamDbs>,
+    active_account: Account,
+    account: String,
+    repo: String,
+    package: String,
+    force: Option<bool>
+) -> Removal
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let package = load_package(&*db, repo.id, &package)?;
+    remove(&*db, &upstream, &repo, vec![package], force)?
+}
+
+/// Removes the packages that are inactive and have newer versions.
+#[throws]
+#[post("/repos/<account>/<repo>/delete-obsolete?<force>")]
+pub fn route_delete_obsolete(
+    db: Db,
+    upstream: State<UpstreamDbs>,
+    active_account: Account,
+    account: String,
+    repo: String,
+    force: Option<bool>
+) -> Removal
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let obsoletes = load_obsolete(&*db, repo.id)?;
+    remove(&*db, &upstream, &repo, obsoletes, force)?
+}
+
+#[throws]
+fn remove(
+    db: &PgConnection,
+    upstream: &UpstreamDbs,
+    repo: &Repo,
+    packages: Vec<models::Package>,
+    force: Option<bool>
+) -> Removal
+{
+    if !force.unwrap_or(false) {
+        let preview = preview_removal(db, upstream, repo.id, &packages)?;
+        if !preview.breakages.is_empty() {
+            return Removal::Refused(Json(preview));
+        }
+    }
+    let jobs = schedule_removal(db, &packages)?.into_iter()
+        .map(|job| augment_job(db, job))
+        .collect::<Result<Vec<_>, _>>()?;
+    Removal::Removed(Json(jobs))
+}
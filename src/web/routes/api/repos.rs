@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+
+use diesel::PgConnection;
+use fehler::throws;
+use rocket::response::status::NoContent;
+use rocket_contrib::json::Json;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::db::delete_repo;
+use crate::db::get_account;
+use crate::db::get_base_repos;
+use crate::db::get_layered_repos;
+use crate::db::get_package_count_by_repo;
+use crate::db::get_package_history;
+use crate::db::get_repos_by_account;
+use crate::db::get_total_package_size_by_repo;
+use crate::db::models::Account;
+use crate::db::models::PackageEvent;
+use crate::db::models::Repo;
+use crate::db::Paginated;
+use crate::jobs::create_check_deps_with_layered;
+use crate::web::db::Db;
+use crate::web::Error;
+use crate::web::routes::api::PAGE_SIZE;
+use crate::web::routes::load_account;
+use crate::web::routes::load_repo;
+use crate::web::routes::repo::create_named_repo;
+use crate::web::routes::repo::load_missing_deps;
+use crate::web::routes::repo::MissingDeps;
+use crate::web::routes::validate_access;
+
+#[derive(Serialize)]
+pub struct RepoInfo {
+    account: String,
+    name: String,
+    package_count: usize,
+    total_size: usize
+}
+
+#[throws]
+fn repo_info(db: &PgConnection, account: &Account, repo: Repo) -> RepoInfo {
+    RepoInfo {
+        account: account.name.clone(),
+        package_count: get_package_count_by_repo(db, repo.id)?,
+        total_size: get_total_package_size_by_repo(db, repo.id)?,
+        name: repo.name
+    }
+}
+
+#[derive(Serialize)]
+pub struct RepoDetails {
+    #[serde(flatten)]
+    repo: RepoInfo,
+    /// The repositories this repository layers on, as `account/repo`.
+    layers: Vec<String>
+}
+
+#[throws]
+#[get("/accounts/<account>/repos?<page>")]
+pub fn route_repos(db: Db, account: String, page: Option<usize>) -> Json<Paginated<RepoInfo>> {
+    let account = load_account(&*db, &account)?;
+    let mut repos = get_repos_by_account(&*db, account.id)?;
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    let repos = Paginated::slice(repos, page.unwrap_or(0), PAGE_SIZE)
+        .try_map(|repo| repo_info(&*db, &account, repo))?;
+    Json(repos)
+}
+
+#[derive(Deserialize)]
+pub struct CreateRepo {
+    name: String
+}
+
+#[throws]
+#[post("/accounts/<account>/repos", data = "<data>")]
+pub fn route_repo_create(db: Db, active_account: Account, account: String, data: Json<CreateRepo>) -> Json<RepoInfo> {
+    let account = validate_access(active_account, account)?;
+    let repo = create_named_repo(&*db, &account, &data.name)?;
+    Json(repo_info(&*db, &account, repo)?)
+}
+
+#[throws]
+#[get("/repos/<account>/<repo>")]
+pub fn route_repo(db: Db, account: String, repo: String) -> Json<RepoDetails> {
+    let account = load_account(&*db, &account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let layers = get_base_repos(&*db, repo.id)?.into_iter()
+        .map(|base| -> Result<String, Error> {
+            let owner = get_account(&*db, base.owner_id)?;
+            Ok(format!("{}/{}", owner.name, base.name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let repo = repo_info(&*db, &account, repo)?;
+    Json(RepoDetails { repo, layers })
+}
+
+/// Deletes the repository with all of its packages and their files. The
+/// repositories that layered on it have their dependencies checked again.
+/// No removals are recorded, since the events of a repository are deleted
+/// with it, so the feeds don't show them.
+#[throws]
+#[delete("/repos/<account>/<repo>")]
+pub fn route_repo_delete(db: Db, active_account: Account, account: String, repo: String) -> NoContent {
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let layered = get_layered_repos(&*db, repo.id)?;
+    let packages = delete_repo(&*db, repo.id)?;
+    fs::remove_file(format!("repos/{}.db.tar.gz", repo.id)).ok();
+    for package in packages {
+        // Files that are already gone, like those of removed packages, or
+        // that can't be removed are left to the `gc` task.
+        fs::remove_file(Path::new("packages").join(&package.archive)).ok();
+        fs::remove_file(Path::new("packages").join(&package.signature)).ok();
+    }
+    for layered in layered {
+        create_check_deps_with_layered(&*db, layered.id)?;
+    }
+    NoContent
+}
+
+#[throws]
+#[get("/repos/<account>/<repo>/missing-deps")]
+pub fn route_missing_deps(db: Db, account: String, repo: String) -> Json<MissingDeps> {
+    let account = load_account(&*db, &account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    Json(load_missing_deps(&*db, repo.id)?)
+}
+
+/// The uploads and removals in the repository, newest first, optionally only
+/// those of the packages with the given name.
+#[throws]
+#[get("/repos/<account>/<repo>/history?<name>&<page>")]
+pub fn route_history(
+    db: Db,
+    account: String,
+    repo: String,
+    name: Option<String>,
+    page: Option<usize>
+) -> Json<Paginated<PackageEvent>>
+{
+    let account = load_account(&*db, &account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    Json(get_package_history(&*db, repo.id, name.as_deref(), page.unwrap_or(0))?)
+}
@@ -0,0 +1,37 @@
+use fehler::throws;
+use rocket::State;
+use rocket_contrib::json::Json;
+use serde::Serialize;
+
+use crate::config::Settings;
+use crate::db::get_account;
+use crate::db::get_packages_by_query;
+use crate::db::get_repo;
+use crate::db::Paginated;
+use crate::web::db::Db;
+use crate::web::Error;
+use crate::web::models::augment_package;
+use crate::web::models::Package;
+use crate::web::routes::api::PAGE_SIZE;
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    account: String,
+    repo: String,
+    package: Package
+}
+
+#[throws]
+#[get("/search?<query>&<page>")]
+pub fn route_search(db: Db, settings: State<Settings>, query: String, page: Option<usize>) -> Json<Paginated<SearchResult>> {
+    let mut packages = get_packages_by_query(&*db, &query)?;
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    let results = Paginated::slice(packages, page.unwrap_or(0), PAGE_SIZE)
+        .try_map(|package| -> Result<SearchResult, Error> {
+            let repo = get_repo(&*db, package.repo_id)?;
+            let account = get_account(&*db, repo.owner_id)?;
+            let package = augment_package(&*db, &settings.aur, package)?;
+            Ok(SearchResult { account: account.name, repo: repo.name, package })
+        })?;
+    Json(results)
+}
@@ -0,0 +1,58 @@
+use fehler::throws;
+use rocket::response::status::NoContent;
+use rocket_contrib::json::Json;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::db::create_token;
+use crate::db::delete_token_for_account;
+use crate::db::get_tokens_for_account;
+use crate::db::models::Account;
+use crate::db::models::NewToken;
+use crate::web::db::Db;
+use crate::web::Error;
+use crate::web::Error::*;
+use crate::web::routes::create_random_token;
+
+/// An access token. The token itself is only shown when it is created.
+#[derive(Serialize)]
+pub struct TokenInfo {
+    id: i32,
+    name: String
+}
+
+#[throws]
+#[get("/tokens")]
+pub fn route_tokens(db: Db, account: Account) -> Json<Vec<TokenInfo>> {
+    let tokens = get_tokens_for_account(&*db, account.id)?.into_iter()
+        .map(|t| TokenInfo { id: t.id, name: t.name })
+        .collect();
+    Json(tokens)
+}
+
+#[derive(Deserialize)]
+pub struct CreateToken {
+    name: String
+}
+
+#[throws]
+#[post("/tokens", data = "<data>")]
+pub fn route_token_create(db: Db, account: Account, data: Json<CreateToken>) -> Json<NewToken> {
+    if data.name.is_empty() {
+        Err(BadRequest("Field 'name' cannot be empty.".into()))?
+    }
+    let token = NewToken {
+        name: data.name.clone(),
+        the_token: create_random_token(),
+        account_id: account.id
+    };
+    create_token(&*db, &token)?;
+    Json(token)
+}
+
+#[throws]
+#[delete("/tokens/<id>", rank = 1)]
+pub fn route_token_delete(db: Db, account: Account, id: i32) -> NoContent {
+    delete_token_for_account(&*db, account.id, id)?;
+    NoContent
+}
@@ -0,0 +1,58 @@
+use fehler::throws;
+use rocket::http::ContentType;
+use rocket::response::Content;
+use rocket::State;
+
+use crate::config::Settings;
+use crate::db::get_package_events_by_account;
+use crate::db::get_package_events_by_repo;
+use crate::feed::Entry;
+use crate::feed::Feed;
+use crate::web::db::Db;
+use crate::web::Error;
+use crate::web::routes::load_account;
+use crate::web::routes::load_repo;
+
+const FEED_SIZE: i64 = 50;
+
+fn atom(feed: Feed) -> Content<String> {
+    Content(ContentType::new("application", "atom+xml"), feed.to_xml())
+}
+
+/// The latest uploads and removals of the repository.
+#[throws]
+#[get("/<account>/<repo>/feed.atom")]
+pub fn route_repo_feed(db: Db, settings: State<Settings>, account: String, repo: String) -> Content<String> {
+    let account = load_account(&*db, &account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let repo_url = format!("{}/{}/{}", settings.url, account.name, repo.name);
+    let entries = get_package_events_by_repo(&*db, repo.id, FEED_SIZE)?.iter()
+        .map(|event| Entry::package_event(&repo_url, &repo.name, event))
+        .collect();
+    atom(Feed {
+        id: format!("{}/feed.atom", repo_url),
+        title: format!("{}/{}", account.name, repo.name),
+        link: repo_url,
+        entries
+    })
+}
+
+/// The latest uploads and removals of all repositories of the account.
+#[throws]
+#[get("/<account>/feed.atom")]
+pub fn route_account_feed(db: Db, settings: State<Settings>, account: String) -> Content<String> {
+    let account = load_account(&*db, &account)?;
+    let account_url = format!("{}/{}", settings.url, account.name);
+    let entries = get_package_events_by_account(&*db, account.id, FEED_SIZE)?.iter()
+        .map(|(event, repo)| {
+            let repo_url = format!("{}/{}", account_url, repo.name);
+            Entry::package_event(&repo_url, &repo.name, event)
+        })
+        .collect();
+    atom(Feed {
+        id: format!("{}/feed.atom", account_url),
+        title: account.name,
+        link: account_url,
+        entries
+    })
+}
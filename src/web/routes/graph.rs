@@ -0,0 +1,84 @@
+use diesel::PgConnection;
+use fehler::throws;
+use rocket::http::ContentType;
+use rocket::response::Content;
+use rocket::State;
+use rocket_contrib::json::Json;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
+
+use crate::graph::dependency_graph;
+use crate::graph::Graph;
+use crate::syncdb::UpstreamDbs;
+use crate::web::ctx_base::BaseContext;
+use crate::web::db::Db;
+use crate::web::Error;
+use crate::web::props::Props;
+use crate::web::routes::load_account;
+use crate::web::routes::load_repo;
+
+#[throws]
+fn load_graph(
+    db: &PgConnection,
+    upstream_dbs: &UpstreamDbs,
+    account: &str,
+    repo: &str,
+    upstream: Option<bool>
+) -> Graph
+{
+    let account = load_account(db, account)?;
+    let repo = load_repo(db, account.id, repo)?;
+    let upstream = if upstream.unwrap_or(false) { Some(upstream_dbs) } else { None };
+    dependency_graph(db, repo.id, upstream)?
+}
+
+#[throws]
+#[get("/<account>/<repo>/graph.json?<upstream>")]
+pub fn route_graph_json(
+    db: Db,
+    upstream_dbs: State<UpstreamDbs>,
+    account: String,
+    repo: String,
+    upstream: Option<bool>
+) -> Json<Graph>
+{
+    Json(load_graph(&*db, &upstream_dbs, &account, &repo, upstream)?)
+}
+
+#[throws]
+#[get("/<account>/<repo>/graph.dot?<upstream>")]
+pub fn route_graph_dot(
+    db: Db,
+    upstream_dbs: State<UpstreamDbs>,
+    account: String,
+    repo: String,
+    upstream: Option<bool>
+) -> Content<String>
+{
+    let graph = load_graph(&*db, &upstream_dbs, &account, &repo, upstream)?;
+    let dot = graph.to_dot(&format!("{}/{}", account, repo));
+    Content(ContentType::new("text", "vnd.graphviz"), dot)
+}
+
+#[derive(Serialize)]
+struct GraphContext {
+    base: BaseContext,
+    account: String,
+    repo: String,
+    upstream: bool
+}
+
+#[throws]
+#[get("/<account>/<repo>/graph?<upstream>")]
+pub fn route_graph_html(props: Props, account: String, repo: String, upstream: Option<bool>) -> Template {
+    let account = load_account(&*props.db, &account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+    let base = BaseContext::new(&props.account);
+    let context = GraphContext {
+        base,
+        account: account.name,
+        repo: repo.name,
+        upstream: upstream.unwrap_or(false)
+    };
+    Template::render("graph", context)
+}
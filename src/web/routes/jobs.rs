@@ -0,0 +1,151 @@
+use std::cmp::min;
+use std::time::Duration;
+
+use diesel::PgConnection;
+use fehler::throws;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_contrib::json::Json;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
+
+use crate::db::delete_job;
+use crate::db::get_job_by_id;
+use crate::db::get_jobs_by_account;
+use crate::db::get_repo;
+use crate::db::models;
+use crate::db::models::Account;
+use crate::db::models::JobStatus;
+use crate::db::retry_job;
+use crate::db::set_package_deleted;
+use crate::jobs::RepoAction;
+use crate::jobs::RepoActionOp;
+use crate::jobs::wait_for_job;
+use crate::web::ctx_base::BaseContext;
+use crate::web::db::Db;
+use crate::web::db::DbPool;
+use crate::web::Error;
+use crate::web::Error::*;
+use crate::web::models::augment_job;
+use crate::web::models::Job;
+use crate::web::props::Props;
+use crate::web::referer::Referer;
+
+#[derive(Serialize)]
+struct JobsContext {
+    base: BaseContext,
+    jobs: Vec<Job>
+}
+
+impl JobsContext {
+    fn new(props: &Props, jobs: Vec<Job>) -> JobsContext {
+        let base = BaseContext::new(&props.account);
+        JobsContext { base, jobs }
+    }
+}
+
+#[throws]
+#[get("/jobs", format = "application/json")]
+pub fn route_jobs_json(db: Db, account: Account) -> Json<Vec<Job>> {
+    Json(load_jobs(&*db, &account)?)
+}
+
+#[throws]
+#[get("/jobs", format = "text/html")]
+pub fn route_jobs_html(props: Props, account: Account) -> Template {
+    let jobs = load_jobs(&*props.db, &account)?;
+    let context = JobsContext::new(&props, jobs);
+    Template::render("jobs", context)
+}
+
+/// Returns the state of a single job. With `wait`, the request is held open
+/// for up to that many seconds until the job has finished or failed.
+#[throws]
+#[get("/jobs/<id>?<wait>")]
+pub fn route_job_status(db: Db, pool: State<DbPool>, account: Account, id: i32, wait: Option<u64>) -> Json<Job> {
+    let job = load_job(&*db, &account, id)?;
+    drop(db);
+    let job = wait_for_job_limited(&pool, job.id, wait)?.ok_or(NotFound)?;
+    Json(augment_job(&*pool.get()?, job)?)
+}
+
+/// How long a request may wait for a job, so that waiting clients cannot
+/// occupy all request threads.
+const MAX_WAIT: u64 = 30;
+
+/// Waits for the job like `wait_for_job`, but never for longer than
+/// `MAX_WAIT` seconds. A connection is only taken from the pool to look up the
+/// job, not while sleeping in between.
+#[throws]
+pub fn wait_for_job_limited(pool: &DbPool, job_id: i32, wait: Option<u64>) -> Option<models::Job> {
+    let timeout = Duration::from_secs(min(wait.unwrap_or(0), MAX_WAIT));
+    wait_for_job(timeout, || -> Result<_, Error> {
+        Ok(get_job_by_id(&*pool.get()?, job_id)?)
+    })?
+}
+
+#[throws]
+#[post("/jobs/<id>/retry")]
+pub fn route_job_retry(db: Db, account: Account, referer: Option<Referer>, id: i32) -> Redirect {
+    let job = load_job(&*db, &account, id)?;
+    retry_failed_job(&*db, &job)?;
+    redirect_back(referer)
+}
+
+#[throws]
+pub fn retry_failed_job(db: &PgConnection, job: &models::Job) {
+    if job.status != JobStatus::Failed {
+        Err(BadRequest("Only failed jobs can be retried.".into()))?
+    }
+    retry_job(db, job.id)?;
+}
+
+#[throws]
+#[delete("/jobs/<id>")]
+pub fn route_job_cancel(db: Db, account: Account, referer: Option<Referer>, id: i32) -> Redirect {
+    let job = load_job(&*db, &account, id)?;
+    cancel_job(&*db, &job)?;
+    redirect_back(referer)
+}
+
+#[throws]
+pub fn cancel_job(db: &PgConnection, job: &models::Job) {
+    if job.status == JobStatus::Running {
+        Err(Conflict)?
+    }
+
+    // A cancelled removal leaves the package in place, so it must not stay
+    // hidden as deleted.
+    if job.tag == "repo_action" {
+        let repo_action: RepoAction = serde_json::from_value(job.spec.clone())?;
+        if repo_action.operation == RepoActionOp::Remove {
+            set_package_deleted(db, repo_action.package_id, false)?;
+        }
+    }
+
+    delete_job(db, job.id)?;
+}
+
+#[throws]
+pub fn load_jobs(db: &PgConnection, account: &Account) -> Vec<Job> {
+    get_jobs_by_account(db, account.id)?.into_iter()
+        .map(|job| augment_job(db, job))
+        .collect::<Result<Vec<_>, _>>()?
+}
+
+#[throws]
+pub fn load_job(db: &PgConnection, account: &Account, id: i32) -> models::Job {
+    let job = get_job_by_id(db, id)?.ok_or(NotFound)?;
+    let repo_id = job.repo_id.ok_or(NotFound)?;
+    if get_repo(db, repo_id)?.owner_id != account.id {
+        Err(NotFound)?
+    }
+    job
+}
+
+fn redirect_back(referer: Option<Referer>) -> Redirect {
+    match referer {
+        Some(referer) => Redirect::to(referer.0),
+        None => Redirect::to("/jobs")
+    }
+}
@@ -20,11 +20,18 @@ pub mod login;
 pub mod logout;
 pub mod account;
 pub mod access_tokens;
+pub mod notifications;
 pub mod repo;
+pub mod graph;
 pub mod package;
 pub mod remove;
 pub mod upload;
 pub mod search;
+pub mod jobs;
+pub mod admin;
+pub mod webhooks;
+pub mod feed;
+pub mod api;
 
 fn hash_password(salt: &str, password: &str) -> String {
     let mut hasher = Sha3_256::new();
@@ -0,0 +1,145 @@
+use chrono::Duration;
+use chrono::Utc;
+use fehler::throws;
+use rocket::request::Form;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
+
+use crate::config::Settings;
+use crate::db::confirm_notification_preference;
+use crate::db::delete_notification_preference;
+use crate::db::get_notification_preference;
+use crate::db::models::Account;
+use crate::db::models::NotificationPreference;
+use crate::db::set_notification_preference;
+use crate::notification::send_confirmation;
+use crate::notification::CONFIRMATION_COOLDOWN_MINUTES;
+use crate::web::ctx_base::BaseContext;
+use crate::web::Error;
+use crate::web::Error::*;
+use crate::web::props::Props;
+
+#[derive(Serialize)]
+struct NotificationsContext {
+    base: BaseContext,
+    /// Whether the account has set up notifications. If it has not, the form
+    /// suggests every kind of notification.
+    enabled: bool,
+    /// Whether the email address is confirmed. Until it is, nothing is sent
+    /// to it but the confirmation link.
+    confirmed: bool,
+    email: String,
+    outdated: bool,
+    missing_deps: bool,
+    failed_jobs: bool,
+    smtp_configured: bool
+}
+
+impl NotificationsContext {
+    fn new(props: &Props, preference: Option<NotificationPreference>, smtp_configured: bool) -> NotificationsContext {
+        let base = BaseContext::new(&props.account);
+        match preference {
+            Some(p) => NotificationsContext {
+                base,
+                enabled: true,
+                confirmed: p.confirmed,
+                email: p.email,
+                outdated: p.outdated,
+                missing_deps: p.missing_deps,
+                failed_jobs: p.failed_jobs,
+                smtp_configured
+            },
+            None => NotificationsContext {
+                base,
+                enabled: false,
+                confirmed: false,
+                email: String::new(),
+                outdated: true,
+                missing_deps: true,
+                failed_jobs: true,
+                smtp_configured
+            }
+        }
+    }
+}
+
+#[throws]
+#[get("/notifications")]
+pub fn route_notifications(props: Props, settings: State<Settings>, account: Account) -> Template {
+    let preference = get_notification_preference(&*props.db, account.id)?;
+    let context = NotificationsContext::new(&props, preference, settings.smtp.is_some());
+    Template::render("notifications", context)
+}
+
+#[derive(FromForm)]
+pub struct SetNotificationPreference {
+    email: String,
+    outdated: bool,
+    missing_deps: bool,
+    failed_jobs: bool
+}
+
+/// Saves the preferences. A new email address has to be confirmed, so a link
+/// is sent to it right away. Links are sent at most once per cooldown, so the
+/// form can't be used to flood an address; otherwise the link is left to the
+/// `send_notifications` task, which also sends it if sending fails here.
+#[throws]
+#[post("/notifications", data = "<data>")]
+pub fn route_notifications_set(
+    props: Props,
+    settings: State<Settings>,
+    account: Account,
+    data: Form<SetNotificationPreference>
+) -> Redirect
+{
+    let email = data.email.trim();
+    if !email.contains('@') {
+        Err(BadRequest("Field 'email' must be an email address.".into()))?
+    }
+
+    let previous = get_notification_preference(&*props.db, account.id)?;
+    let recently_sent = previous.as_ref()
+        .and_then(|p| p.confirmation_sent)
+        .map_or(false, |sent| Utc::now().naive_utc() - sent < Duration::minutes(CONFIRMATION_COOLDOWN_MINUTES));
+    let changed = previous.as_ref().map_or(true, |p| p.email != email);
+    let previous = previous.filter(|_| !changed);
+    let preference = NotificationPreference {
+        account_id: account.id,
+        email: email.to_owned(),
+        outdated: data.outdated,
+        missing_deps: data.missing_deps,
+        failed_jobs: data.failed_jobs,
+        confirmed: previous.as_ref().map_or(false, |p| p.confirmed),
+        confirmation_token: previous.as_ref().and_then(|p| p.confirmation_token.clone()),
+        confirmation_sent: previous.as_ref().and_then(|p| p.confirmation_sent)
+    };
+    set_notification_preference(&*props.db, &preference)?;
+
+    let resend = changed || preference.confirmation_sent.is_some();
+    if !preference.confirmed && !recently_sent && resend {
+        if let Some(smtp) = &settings.smtp {
+            if let Err(error) = send_confirmation(&*props.db, smtp, &settings.url, &preference) {
+                println!("{}", error);
+            }
+        }
+    }
+    Redirect::to("/notifications")
+}
+
+#[throws]
+#[get("/notifications/confirm/<token>")]
+pub fn route_notifications_confirm(props: Props, token: String) -> Redirect {
+    if !confirm_notification_preference(&*props.db, &token)? {
+        Err(NotFound)?
+    }
+    Redirect::to("/notifications")
+}
+
+#[throws]
+#[delete("/notifications")]
+pub fn route_notifications_delete(props: Props, account: Account) -> Redirect {
+    delete_notification_preference(&*props.db, account.id)?;
+    Redirect::to("/notifications")
+}
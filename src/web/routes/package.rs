@@ -1,26 +1,53 @@
 use std::fs::File;
 
+use diesel::PgConnection;
 use fehler::throws;
 use log::info;
 use regex::Regex;
 use rocket::http::ContentType;
 use rocket::response::Content;
+use rocket::request::Form;
 use rocket::response::Redirect;
+use rocket::State;
+use rocket_contrib::json::Json;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
 
+use crate::config::Aur;
+use crate::config::Settings;
+use crate::db::delete_version_source;
+use crate::db::get_account;
+use crate::db::get_package;
+use crate::db::get_package_depends;
+use crate::db::get_package_provides;
+use crate::db::get_repo;
+use crate::db::models::Account;
+use crate::db::models::DependencyKind;
+use crate::db::models::NewVersionSource;
+use crate::db::models::VersionSourceKind;
+use crate::db::set_version_source;
+use crate::format_pkg_filename;
 use crate::jobs::create_repo_action;
 use crate::jobs::RepoActionOp;
+use crate::rdeps::required_by;
 use crate::serve_archive;
 use crate::serve_db;
+use crate::version_source::is_https_url;
+use crate::web::ctx_base::BaseContext;
 use crate::web::db::Db;
 use crate::web::Error;
 use crate::web::Error::*;
+use crate::web::models::augment_package;
+use crate::web::models::Package;
+use crate::web::props::Props;
 use crate::web::referer::Referer;
 use crate::web::routes::load_account;
 use crate::web::routes::load_package;
 use crate::web::routes::load_repo;
+use crate::web::routes::validate_access;
 
 #[throws]
-#[get("/<account>/<repo>/<file>")]
+#[get("/<account>/<repo>/<file>", rank = 2)]
 pub fn route_get_package(db: Db, account: String, repo: String, file: String) -> Content<File> {
     let account = load_account(&*db, &account)?;
     let repo = load_repo(&*db, account.id, &repo)?;
@@ -40,6 +67,82 @@ pub fn route_get_package(db: Db, account: String, repo: String, file: String) ->
     Content(ContentType::Binary, file)
 }
 
+#[derive(FromForm)]
+pub struct SetVersionSource {
+    kind: String,
+    source: String,
+    pattern: String
+}
+
+/// Sets where the latest upstream version of the package is looked up. Since
+/// commands run on the server, only admins may configure them.
+#[throws]
+#[post("/<account>/<repo>/<package>/version-source", data = "<data>")]
+pub fn route_version_source_set(
+    db: Db,
+    settings: State<Settings>,
+    active_account: Account,
+    account: String,
+    repo: String,
+    package: String,
+    data: Form<SetVersionSource>
+) -> Redirect
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let package = load_package(&*db, repo.id, &package)?;
+
+    let kind: VersionSourceKind = data.kind.parse()
+        .map_err(|_| BadRequest(format!("Unknown version source '{}'.", data.kind)))?;
+    let source = data.source.trim();
+    let pattern = Some(data.pattern.trim()).filter(|p| !p.is_empty());
+    if source.is_empty() {
+        Err(BadRequest("Field 'source' cannot be empty.".into()))?
+    }
+    if let Some(pattern) = pattern {
+        Regex::new(pattern)
+            .map_err(|e| BadRequest(format!("Field 'pattern' is not a valid regex: {}", e)))?;
+    }
+    match kind {
+        VersionSourceKind::GitHub if source.split('/').count() != 2 =>
+            Err(BadRequest("Field 'source' must have the form 'owner/repo'.".into()))?,
+        VersionSourceKind::GitLab | VersionSourceKind::Git if !is_https_url(source) =>
+            Err(BadRequest("Field 'source' must be an HTTPS URL.".into()))?,
+        VersionSourceKind::Regex if pattern.is_none() =>
+            Err(BadRequest("A regex version source needs a pattern.".into()))?,
+        VersionSourceKind::Command if !settings.is_admin(&account.name) =>
+            Err(Forbidden)?,
+        _ => ()
+    }
+
+    set_version_source(&*db, &NewVersionSource {
+        repo_id: repo.id,
+        package: package.name.clone(),
+        kind,
+        source: source.to_owned(),
+        pattern: pattern.map(|p| p.to_owned())
+    })?;
+
+    Redirect::to(format!("/{}/{}/{}/details", account.name, repo.name, format_pkg_filename(&package)))
+}
+
+#[throws]
+#[delete("/<account>/<repo>/<package>/version-source")]
+pub fn route_version_source_remove(
+    db: Db,
+    active_account: Account,
+    account: String,
+    repo: String,
+    package: String
+) -> Redirect
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let package = load_package(&*db, repo.id, &package)?;
+    delete_version_source(&*db, repo.id, &package.name)?;
+    Redirect::to(format!("/{}/{}/{}/details", account.name, repo.name, format_pkg_filename(&package)))
+}
+
 #[throws]
 #[post("/<account>/<repo>/<package>/activate")]
 pub fn route_activate_package(db: Db, referrer: Referer, account: String, repo: String, package: String) -> Redirect {
@@ -49,3 +152,89 @@ pub fn route_activate_package(db: Db, referrer: Referer, account: String, repo:
     create_repo_action(&*db, package.id, RepoActionOp::Add)?;
     Redirect::to(referrer.0)
 }
+
+#[derive(Serialize)]
+pub struct PackageDetails {
+    #[serde(flatten)]
+    package: Package,
+    depends: Vec<Dependency>,
+    optdepends: Vec<Dependency>,
+    makedepends: Vec<Dependency>,
+    checkdepends: Vec<Dependency>,
+    provides: Vec<String>,
+    required_by: Vec<RequiredBy>
+}
+
+#[derive(Serialize)]
+struct Dependency {
+    depends: String,
+    missing: bool
+}
+
+#[derive(Serialize)]
+struct RequiredBy {
+    repo: String,
+    package: String,
+    name: String,
+    dependency: String
+}
+
+#[derive(Serialize)]
+struct PackageContext {
+    base: BaseContext,
+    account: String,
+    repo: String,
+    package: PackageDetails
+}
+
+#[throws]
+#[get("/<account>/<repo>/<package>/details", format = "application/json")]
+pub fn route_package_json(db: Db, settings: State<Settings>, account: String, repo: String, package: String) -> Json<PackageDetails> {
+    Json(load_package_details(&*db, &settings.aur, &account, &repo, &package)?)
+}
+
+#[throws]
+#[get("/<account>/<repo>/<package>/details", format = "text/html")]
+pub fn route_package_html(props: Props, settings: State<Settings>, account: String, repo: String, package: String) -> Template {
+    let package = load_package_details(&*props.db, &settings.aur, &account, &repo, &package)?;
+    let base = BaseContext::new(&props.account);
+    let context = PackageContext { base, account, repo, package };
+    Template::render("package", context)
+}
+
+#[throws]
+pub fn load_package_details(db: &PgConnection, aur: &Aur, account: &str, repo: &str, package: &str) -> PackageDetails {
+    let account = load_account(db, account)?;
+    let repo = load_repo(db, account.id, repo)?;
+    let package = load_package(db, repo.id, package)?;
+    let provides = get_package_provides(db, package.id)?.into_iter()
+        .map(|p| p.provides)
+        .collect();
+    let package_depends = get_package_depends(db, package.id)?;
+    let required_by = required_by(db, &package)?.into_iter()
+        .map(|requirement| -> Result<RequiredBy, Error> {
+            let dependent = get_package(db, requirement.package_id)?;
+            let repo = get_repo(db, dependent.repo_id)?;
+            let account = get_account(db, repo.owner_id)?;
+            Ok(RequiredBy {
+                repo: format!("{}/{}", account.name, repo.name),
+                package: format_pkg_filename(&dependent),
+                name: dependent.name,
+                dependency: requirement.dependency
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let package = augment_package(db, aur, package)?;
+    let dependencies = |kind: DependencyKind, missing: &[String]| package_depends.iter()
+        .filter(|d| d.kind == kind)
+        .map(|d| Dependency {
+            missing: missing.contains(&d.depends),
+            depends: d.depends.clone()
+        })
+        .collect::<Vec<_>>();
+    let optdepends = dependencies(DependencyKind::OptDepend, &package.missing_optdepends);
+    let makedepends = dependencies(DependencyKind::MakeDepend, &package.missing_makedepends);
+    let checkdepends = dependencies(DependencyKind::CheckDepend, &package.missing_checkdepends);
+    let depends = dependencies(DependencyKind::Depend, &package.missing_deps);
+    PackageDetails { package, depends, optdepends, makedepends, checkdepends, provides, required_by }
+}
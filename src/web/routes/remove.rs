@@ -1,40 +1,211 @@
+use diesel::PgConnection;
 use fehler::throws;
 use rocket::response::Redirect;
+use rocket::State;
+use rocket_contrib::json::Json;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
 
-use crate::db::get_package_by_repo;
-use crate::db::get_repo_by_account_and_name;
+use crate::db::get_account;
+use crate::db::get_package;
+use crate::db::get_repo;
+use crate::db::models;
 use crate::db::models::Account;
+use crate::db::models::Package;
+use crate::db::models::Repo;
 use crate::db::set_package_deleted;
+use crate::format_pkg_filename;
 use crate::jobs::create_repo_action;
 use crate::jobs::RepoActionOp;
-use crate::parse_pkg_filename;
+use crate::rdeps::removal_impact;
+use crate::syncdb::UpstreamDbs;
+use crate::web::ctx_base::BaseContext;
 use crate::web::db::Db;
 use crate::web::Error;
-use crate::web::Error::*;
+use crate::web::props::Props;
+use crate::web::routes::load_account;
+use crate::web::routes::load_package;
+use crate::web::routes::load_repo;
 use crate::web::routes::validate_access;
 
+/// The outcome of a removal. A removal that would leave other packages with
+/// unsatisfied dependencies is refused, unless it is forced, and the refusal
+/// shows what would break.
+#[derive(Responder)]
+pub enum Removal {
+    Removed(Redirect),
+    #[response(status = 409)]
+    Refused(Template),
+}
+
+#[derive(Serialize)]
+pub struct RemovalPreview {
+    pub packages: Vec<String>,
+    pub breakages: Vec<BrokenPackage>
+}
+
+#[derive(Serialize)]
+pub struct BrokenPackage {
+    repo: String,
+    package: String,
+    name: String,
+    arch: String,
+    dependency: String
+}
+
+/// Where the confirmation of a refused removal is sent to.
+pub struct RemovalForm {
+    pub action: String,
+    pub method: &'static str
+}
+
+#[derive(Serialize)]
+struct RemovalContext {
+    base: BaseContext,
+    account: String,
+    repo: String,
+    preview: RemovalPreview,
+    action: String,
+    method: String
+}
+
 #[throws]
-#[delete("/<account>/<repo>/<package>")]
-pub fn route_remove(
+#[get("/<account>/<repo>/<package>/remove", format = "application/json")]
+pub fn route_remove_preview_json(
     db: Db,
+    upstream: State<UpstreamDbs>,
+    account: String,
+    repo: String,
+    package: String
+) -> Json<RemovalPreview>
+{
+    let account = load_account(&*db, &account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let package = load_package(&*db, repo.id, &package)?;
+    Json(preview_removal(&*db, &upstream, repo.id, &[package])?)
+}
+
+#[throws]
+#[get("/<account>/<repo>/<package>/remove", format = "text/html")]
+pub fn route_remove_preview_html(
+    props: Props,
+    upstream: State<UpstreamDbs>,
+    account: String,
+    repo: String,
+    package: String
+) -> Template
+{
+    let account = load_account(&*props.db, &account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+    let package = load_package(&*props.db, repo.id, &package)?;
+    let form = removal_form(&account, &repo, &package);
+    let preview = preview_removal(&*props.db, &upstream, repo.id, &[package])?;
+    render_preview(&props, &account, &repo, preview, form)
+}
+
+#[throws]
+#[delete("/<account>/<repo>/<package>?<force>")]
+pub fn route_remove(
+    props: Props,
+    upstream: State<UpstreamDbs>,
     active_account: Account,
     account: String,
     repo: String,
     package: String,
-) -> Redirect
+    force: Option<bool>
+) -> Removal
 {
     let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+    let package = load_package(&*props.db, repo.id, &package)?;
 
-    let repo = get_repo_by_account_and_name(&*db, account.id, &repo)?
-        .ok_or(NotFound)?;
+    let form = removal_form(&account, &repo, &package);
+    remove_packages(&props, &upstream, &account, &repo, vec![package], force, form)?
+}
 
-    let (name, version, arch, _) = parse_pkg_filename(&package)
-        .map_err(|_| NotFound)?;
-    let package = get_package_by_repo(&*db, repo.id, &name, &version, &arch)?
-        .ok_or(NotFound)?;
+fn removal_form(account: &Account, repo: &Repo, package: &Package) -> RemovalForm {
+    let action = format!("/{}/{}/{}?force=true", account.name, repo.name, format_pkg_filename(package));
+    RemovalForm { action, method: "delete" }
+}
 
-    set_package_deleted(&*db, package.id, true)?;
-    create_repo_action(&*db, package.id, RepoActionOp::Remove)?;
+/// Removes the packages, unless that would break other packages and the
+/// removal is not forced.
+#[throws]
+pub fn remove_packages(
+    props: &Props,
+    upstream: &UpstreamDbs,
+    account: &Account,
+    repo: &Repo,
+    packages: Vec<Package>,
+    force: Option<bool>,
+    form: RemovalForm
+) -> Removal
+{
+    if !force.unwrap_or(false) {
+        let preview = preview_removal(&*props.db, upstream, repo.id, &packages)?;
+        if !preview.breakages.is_empty() {
+            let template = render_preview(props, account, repo, preview, form)?;
+            return Removal::Refused(template);
+        }
+    }
 
-    Redirect::to(format!("/{}/{}", account.name, repo.name))
+    schedule_removal(&*props.db, &packages)?;
+    Removal::Removed(Redirect::to(format!("/{}/{}", account.name, repo.name)))
+}
+
+/// Hides the packages and schedules their removal from the repository.
+#[throws]
+pub fn schedule_removal(db: &PgConnection, packages: &[Package]) -> Vec<models::Job> {
+    packages.iter()
+        .map(|package| -> Result<models::Job, Error> {
+            set_package_deleted(db, package.id, true)?;
+            Ok(create_repo_action(db, package.id, RepoActionOp::Remove)?)
+        })
+        .collect::<Result<Vec<_>, _>>()?
+}
+
+#[throws]
+fn render_preview(
+    props: &Props,
+    account: &Account,
+    repo: &Repo,
+    preview: RemovalPreview,
+    form: RemovalForm
+) -> Template
+{
+    let base = BaseContext::new(&props.account);
+    let context = RemovalContext {
+        base,
+        account: account.name.clone(),
+        repo: repo.name.clone(),
+        preview,
+        action: form.action,
+        method: form.method.to_owned()
+    };
+    Template::render("remove", context)
+}
+
+#[throws]
+pub fn preview_removal(db: &PgConnection, upstream: &UpstreamDbs, repo_id: i32, packages: &[Package]) -> RemovalPreview {
+    let removed = packages.iter()
+        .map(|p| p.id)
+        .collect::<Vec<_>>();
+    let breakages = removal_impact(db, upstream, repo_id, &removed)?.into_iter()
+        .map(|breakage| -> Result<BrokenPackage, Error> {
+            let package = get_package(db, breakage.package_id)?;
+            let repo = get_repo(db, package.repo_id)?;
+            let account = get_account(db, repo.owner_id)?;
+            Ok(BrokenPackage {
+                repo: format!("{}/{}", account.name, repo.name),
+                package: format_pkg_filename(&package),
+                name: package.name,
+                arch: breakage.arch,
+                dependency: breakage.dependency
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let packages = packages.iter()
+        .map(format_pkg_filename)
+        .collect();
+    RemovalPreview { packages, breakages }
 }
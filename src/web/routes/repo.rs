@@ -4,43 +4,58 @@ use std::cmp::min;
 use colored::*;
 use diesel::PgConnection;
 use fehler::throws;
+use itertools::Itertools;
 use prettytable::Cell;
 use prettytable::format::consts::FORMAT_CLEAN;
 use prettytable::Row;
 use prettytable::Table;
 use rocket::request::Form;
 use rocket::response::Redirect;
+use rocket::State;
 use rocket_contrib::templates::Template;
 use serde::Serialize;
 
+use crate::arch::repo_archs;
+use crate::config::Aur;
+use crate::config::Settings;
 use crate::db::create_repo;
+use crate::db::create_repo_layer;
+use crate::db::delete_repo_layer;
 use crate::db::ExpectConflict;
+use crate::db::get_account;
 use crate::db::get_all_packages_by_repo;
+use crate::db::get_base_repos;
 use crate::db::get_missing_deps;
+use crate::db::get_needed_rebuilds;
 use crate::db::get_packages_by_repo;
 use crate::db::models::Account;
+use crate::db::models::DependencyKind;
 use crate::db::models::NewRepo;
 use crate::db::models::Repo;
 use crate::db::Paginated;
-use crate::db::set_package_deleted;
-use crate::jobs::create_repo_action;
-use crate::jobs::RepoActionOp;
+use crate::jobs::create_check_deps;
+use crate::format_pkg_filename;
 use crate::obsolete::determine_obsolete;
+use crate::rebuild::rebuild_plan;
+use crate::syncdb::UpstreamDbs;
 use crate::web::ctx_base::BaseContext;
 use crate::web::db::Db;
 use crate::web::Error;
 use crate::web::Error::*;
-use crate::web::models::augment_package;
+use crate::web::models::augment_package_with_missing;
 use crate::web::models::Package;
 use crate::web::props::Props;
 use crate::web::routes::load_account;
 use crate::web::routes::load_repo;
+use crate::web::routes::remove::remove_packages;
+use crate::web::routes::remove::Removal;
+use crate::web::routes::remove::RemovalForm;
 use crate::web::routes::validate_access;
 
 #[throws]
 #[get("/<account>/<repo>", format = "text/plain", rank = 5)]
-pub fn route_repo_text(db: Db, account: String, repo: String) -> String {
-    let (_, _, packages) = get_packages(&*db, &account, &repo, 0)?;
+pub fn route_repo_text(db: Db, settings: State<Settings>, account: String, repo: String) -> String {
+    let (_, _, packages) = get_packages(&*db, &settings.aur, &account, &repo, 0)?;
 
     let mut table = Table::new();
     table.set_format(*FORMAT_CLEAN);
@@ -59,7 +74,9 @@ struct RepoContext {
     account: Account,
     repo: Repo,
     packages: Paginated<Package>,
-    missing_deps: Vec<String>,
+    missing_deps: MissingDeps,
+    rebuild_plan: Vec<Vec<Rebuild>>,
+    layers: Vec<Layer>,
     pages: Vec<usize>,
     can_edit: bool
 }
@@ -70,7 +87,9 @@ impl RepoContext {
         account: Account,
         repo: Repo,
         packages: Paginated<Package>,
-        missing_deps: Vec<String>
+        missing_deps: MissingDeps,
+        rebuild_plan: Vec<Vec<Rebuild>>,
+        layers: Vec<Layer>
     ) -> RepoContext {
         let base = BaseContext::new(&props.account);
         let mut first_page = packages.current_page as isize - 3;
@@ -89,26 +108,143 @@ impl RepoContext {
         let can_edit = if let Some(active_account) = &props.account {
             active_account.name == account.name
         } else { false };
-        RepoContext { base, account, repo, packages, missing_deps, pages, can_edit }
+        RepoContext { base, account, repo, packages, missing_deps, rebuild_plan, layers, pages, can_edit }
     }
 }
 
 #[throws]
 #[get("/<account>/<repo>?<p>", format = "text/html", rank = 4)]
-pub fn route_repo_html(props: Props, account: String, repo: String, p: Option<usize>) -> Template {
-    let (account, repo, packages) = get_packages(&*props.db, &account, &repo, p.unwrap_or(0))?;
-    let missing_deps = get_missing_deps(&*props.db, repo.id)?;
-    let context = RepoContext::new(&props, account, repo, packages, missing_deps);
+pub fn route_repo_html(props: Props, settings: State<Settings>, account: String, repo: String, p: Option<usize>) -> Template {
+    let (account, repo, packages) = get_packages(&*props.db, &settings.aur, &account, &repo, p.unwrap_or(0))?;
+    let missing_deps = load_missing_deps(&*props.db, repo.id)?;
+    let rebuild_plan = load_rebuild_plan(&*props.db, repo.id)?;
+    let layers = load_layers(&*props.db, repo.id)?;
+    let context = RepoContext::new(&props, account, repo, packages, missing_deps, rebuild_plan, layers);
     Template::render("repo", context)
 }
 
+/// A repository that the repository layers on.
+#[derive(Serialize)]
+struct Layer {
+    account: String,
+    repo: String
+}
+
 #[throws]
-fn get_packages(db: &PgConnection, account: &str, repo: &str, page: usize) -> (Account, Repo, Paginated<Package>) {
+fn load_layers(db: &PgConnection, repo_id: i32) -> Vec<Layer> {
+    get_base_repos(db, repo_id)?.into_iter()
+        .map(|repo| -> Result<Layer, Error> {
+            let account = get_account(db, repo.owner_id)?;
+            Ok(Layer { account: account.name, repo: repo.name })
+        })
+        .collect::<Result<Vec<_>, _>>()?
+}
+
+/// The missing dependencies of a repository by kind. Only missing runtime
+/// dependencies prevent packages from being installed.
+#[derive(Serialize)]
+pub struct MissingDeps {
+    runtime: Vec<MissingDep>,
+    optional: Vec<MissingDep>,
+    make: Vec<MissingDep>,
+    check: Vec<MissingDep>
+}
+
+#[derive(Serialize)]
+pub struct MissingDep {
+    dependency: String,
+    /// The architectures the dependency is missing for, unless it is missing
+    /// for all architectures of the repository.
+    archs: Vec<String>,
+    /// The packages that need the dependency.
+    packages: Vec<String>
+}
+
+#[throws]
+pub fn load_missing_deps(db: &PgConnection, repo_id: i32) -> MissingDeps {
+    let archs = repo_archs(db, repo_id)?;
+    let missing_deps = get_missing_deps(db, repo_id)?;
+    let of_kind = |kind: DependencyKind| group_missing_deps(&archs, missing_deps.iter()
+        .filter(|(m, _)| m.kind == kind));
+    MissingDeps {
+        runtime: of_kind(DependencyKind::Depend),
+        optional: of_kind(DependencyKind::OptDepend),
+        make: of_kind(DependencyKind::MakeDepend),
+        check: of_kind(DependencyKind::CheckDepend)
+    }
+}
+
+fn group_missing_deps<'a>(
+    archs: &[String],
+    missing_deps: impl Iterator<Item = &'a (crate::db::models::MissingDep, crate::db::models::Package)>
+) -> Vec<MissingDep> {
+    let missing_deps = missing_deps
+        .group_by(|(m, _)| m.dependency.clone());
+    missing_deps.into_iter()
+        .map(|(dependency, missing)| {
+            let missing = missing.collect::<Vec<_>>();
+            let mut missing_archs = missing.iter()
+                .map(|(m, _)| m.arch.clone())
+                .unique()
+                .collect::<Vec<_>>();
+            if missing_archs.len() >= archs.len() {
+                missing_archs.clear();
+            }
+            let packages = missing.iter()
+                .map(|(_, p)| p.name.clone())
+                .unique()
+                .collect();
+            MissingDep { dependency, archs: missing_archs, packages }
+        })
+        .collect()
+}
+
+/// A package that needs to be rebuilt.
+#[derive(Serialize)]
+struct Rebuild {
+    name: String,
+    archive: String,
+    /// The dependencies that are no longer satisfied.
+    reasons: Vec<String>
+}
+
+#[throws]
+fn load_rebuild_plan(db: &PgConnection, repo_id: i32) -> Vec<Vec<Rebuild>> {
+    let needed_rebuilds = get_needed_rebuilds(db, repo_id)?;
+    let package_ids = needed_rebuilds.iter()
+        .map(|(_, p)| p.id)
+        .unique()
+        .collect::<Vec<_>>();
+    rebuild_plan(db, repo_id, &package_ids)?.into_iter()
+        .map(|stage| {
+            let mut stage = stage.into_iter()
+                .map(|package_id| {
+                    let rebuilds = needed_rebuilds.iter()
+                        .filter(|(_, p)| p.id == package_id)
+                        .collect::<Vec<_>>();
+                    let package = &rebuilds[0].1;
+                    let reasons = rebuilds.iter()
+                        .map(|(r, _)| format!("{} ({}), now {}", r.dependency, r.arch, r.available))
+                        .collect();
+                    Rebuild { name: package.name.clone(), archive: format_pkg_filename(package), reasons }
+                })
+                .collect::<Vec<_>>();
+            stage.sort_by(|a, b| a.name.cmp(&b.name));
+            stage
+        })
+        .collect()
+}
+
+#[throws]
+pub fn get_packages(db: &PgConnection, aur: &Aur, account: &str, repo: &str, page: usize) -> (Account, Repo, Paginated<Package>) {
     let account = load_account(db, account)?;
     let repo = load_repo(db, account.id, repo)?;
     let mut packages = get_packages_by_repo(db, repo.id, page)?;
     packages.items.sort_by_key(|p| p.name.clone());
-    let packages = packages.try_map(|p| augment_package(db, p))?;
+    let missing = get_missing_deps(db, repo.id)?.into_iter()
+        .map(|(m, _)| m)
+        .collect::<Vec<_>>();
+    let packages = packages.try_map(|p| augment_package_with_missing(db, aur, p, &missing))?;
     (account, repo, packages)
 }
 
@@ -127,31 +263,108 @@ pub fn route_repo_create(
 ) -> Redirect
 {
     let account = validate_access(active_account, account)?;
+    let repo = create_named_repo(&*db, &account, &data.name)?;
+    Redirect::to(format!("/{}/{}", account.name, repo.name))
+}
 
-    if data.name.is_empty() {
+#[throws]
+pub fn create_named_repo(db: &PgConnection, account: &Account, name: &str) -> Repo {
+    if name.is_empty() {
         Err(BadRequest("Field 'name' cannot be empty.".into()))?
     }
 
-    let repo = NewRepo { name: data.name.clone(), owner_id: account.id };
-    let repo = create_repo(&*db, &repo)
+    let repo = NewRepo { name: name.to_owned(), owner_id: account.id };
+    create_repo(db, &repo)
+        .expect_conflict()?
+        .ok_or(Conflict)?
+}
+
+#[derive(FromForm)]
+pub struct AddLayer {
+    base: String
+}
+
+#[throws]
+#[post("/<account>/<repo>/layers", data = "<data>", rank = 4)]
+pub fn route_layer_add(
+    db: Db,
+    active_account: Account,
+    account: String,
+    repo: String,
+    data: Form<AddLayer>
+) -> Redirect
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+
+    let parts = data.base.trim().splitn(2, '/').collect::<Vec<_>>();
+    if parts.len() != 2 {
+        Err(BadRequest("Field 'base' must have the form 'account/repo'.".into()))?
+    }
+    let base_account = load_account(&*db, parts[0])?;
+    let base_repo = load_repo(&*db, base_account.id, parts[1])?;
+    if base_repo.id == repo.id {
+        Err(BadRequest("A repository cannot layer on itself.".into()))?
+    }
+
+    create_repo_layer(&*db, repo.id, base_repo.id)
         .expect_conflict()?
         .ok_or(Conflict)?;
+    create_check_deps(&*db, repo.id)?;
 
     Redirect::to(format!("/{}/{}", account.name, repo.name))
 }
 
 #[throws]
-#[post("/<account>/<repo>/delete-obsolete", rank = 4)]
-pub fn route_delete_obsolete(props: Props, account: String, repo: String) -> Redirect {
-    let account = load_account(&*props.db, &account)?;
-    let repo = load_repo(&*props.db, account.id, &repo)?;
-    let packages = get_all_packages_by_repo(&*props.db, repo.id)?;
-    let obsoletes = determine_obsolete(packages.iter().collect());
+#[delete("/<account>/<repo>/layers/<base_account>/<base_repo>")]
+pub fn route_layer_remove(
+    db: Db,
+    active_account: Account,
+    account: String,
+    repo: String,
+    base_account: String,
+    base_repo: String
+) -> Redirect
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*db, account.id, &repo)?;
+    let base_account = load_account(&*db, &base_account)?;
+    let base_repo = load_repo(&*db, base_account.id, &base_repo)?;
 
-    for obsolete in obsoletes {
-        set_package_deleted(&*props.db, obsolete.id, true)?;
-        create_repo_action(&*props.db, obsolete.id, RepoActionOp::Remove)?;
-    }
+    delete_repo_layer(&*db, repo.id, base_repo.id)?;
+    create_check_deps(&*db, repo.id)?;
 
     Redirect::to(format!("/{}/{}", account.name, repo.name))
 }
+
+#[throws]
+#[post("/<account>/<repo>/delete-obsolete?<force>", rank = 4)]
+pub fn route_delete_obsolete(
+    props: Props,
+    upstream: State<UpstreamDbs>,
+    active_account: Account,
+    account: String,
+    repo: String,
+    force: Option<bool>
+) -> Removal
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+    let obsoletes = load_obsolete(&*props.db, repo.id)?;
+
+    let action = format!("/{}/{}/delete-obsolete?force=true", account.name, repo.name);
+    let form = RemovalForm { action, method: "post" };
+    remove_packages(&props, &upstream, &account, &repo, obsoletes, force, form)?
+}
+
+/// The packages that are inactive and have newer versions in the repository.
+#[throws]
+pub fn load_obsolete(db: &PgConnection, repo_id: i32) -> Vec<crate::db::models::Package> {
+    let packages = get_all_packages_by_repo(db, repo_id)?;
+    let obsoletes = determine_obsolete(packages.iter().collect()).into_iter()
+        .map(|p| p.id)
+        .collect::<Vec<_>>();
+    packages.into_iter()
+        .filter(|p| obsoletes.contains(&p.id))
+        .collect()
+}
@@ -1,7 +1,9 @@
 use fehler::throws;
+use rocket::State;
 use rocket_contrib::templates::Template;
 use serde::Serialize;
 
+use crate::config::Settings;
 use crate::db::get_account;
 use crate::db::get_packages_by_query;
 use crate::db::get_repo;
@@ -41,11 +43,11 @@ struct PackageRepoAccount {
 
 #[throws]
 #[get("/search?<query>")]
-pub fn route_search_results(props: Props, query: String) -> Template {
+pub fn route_search_results(props: Props, settings: State<Settings>, query: String) -> Template {
     let results = get_packages_by_query(&props.db, &query)?
         .into_iter()
         .map(|package| {
-            let package = augment_package(&props.db, package)?;
+            let package = augment_package(&props.db, &settings.aur, package)?;
             let repo = get_repo(&props.db, package.repo_id)?;
             let account = get_account(&props.db, repo.owner_id)?;
             Ok(PackageRepoAccount { package, repo, account })
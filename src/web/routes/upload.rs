@@ -5,16 +5,24 @@ use fehler::throws;
 use log::info;
 use multipart::server::Multipart;
 use rocket::Data;
+use rocket::State;
+use rocket_contrib::json::Json;
+use serde::Serialize;
 use uuid::Uuid;
 
 use crate::db::create_package;
+use crate::db::create_package_event;
 use crate::db::create_package_depends;
 use crate::db::create_package_provides;
 use crate::db::ExpectConflict;
 use crate::db::get_package_by_repo;
 use crate::db::get_repo_by_account_and_name;
 use crate::db::models::Account;
+use crate::db::models::DependencyKind;
+use crate::db::models::WebhookEvent;
 use crate::db::models::NewPackage;
+use crate::db::models::NewPackageEvent;
+use crate::db::models::PackageEventKind;
 use crate::jobs::create_repo_action;
 use crate::jobs::RepoActionOp;
 use crate::parse_pkg_filename;
@@ -22,21 +30,79 @@ use crate::pkginfo::load_pkginfo;
 use crate::save_archive;
 use crate::web::boundary::Boundary;
 use crate::web::db::Db;
+use crate::web::db::DbPool;
 use crate::web::Error;
 use crate::web::Error::*;
+use crate::web::models::augment_job;
+use crate::web::models::Job;
+use crate::web::routes::jobs::wait_for_job_limited;
 use crate::web::routes::validate_access;
+use crate::webhook::package_data;
+use crate::webhook::trigger_webhooks;
 
+#[derive(Serialize)]
+pub struct UploadResponse {
+    package: UploadedPackage,
+    job: Job
+}
+
+#[derive(Serialize)]
+struct UploadedPackage {
+    id: i32,
+    name: String,
+    version: String,
+    arch: String,
+    size: i32,
+    description: Option<String>,
+    url: Option<String>,
+    licenses: Vec<String>,
+    depends: Vec<String>,
+    optdepends: Vec<String>,
+    makedepends: Vec<String>,
+    checkdepends: Vec<String>,
+    provides: Vec<String>
+}
+
+/// Stores the uploaded package and schedules its activation. The response
+/// describes the package and the activation job; with `wait`, it is only sent
+/// once the job has finished or failed (or the timeout has elapsed).
 #[throws]
-#[post("/<account>/<repo>/<package>", data = "<data>", rank = 5)]
-pub fn upload(db: Db, active_account: Account, account: String, repo: String, package: String, boundary: Boundary, data: Data) {
+#[post("/<account>/<repo>/<package>?<wait>", data = "<data>", rank = 5)]
+pub fn upload(
+    db: Db,
+    pool: State<DbPool>,
+    active_account: Account,
+    account: String,
+    repo: String,
+    package: String,
+    wait: Option<u64>,
+    boundary: Boundary,
+    data: Data
+) -> Json<UploadResponse>
+{
     let account = validate_access(active_account, account)?;
+    Json(upload_package(db, &pool, &account, &repo, &package, wait, &boundary.0, data)?)
+}
 
-    let repo = get_repo_by_account_and_name(&*db, account.id, &repo)?
+#[throws]
+pub fn upload_package(
+    conn: Db,
+    pool: &DbPool,
+    account: &Account,
+    repo: &str,
+    package: &str,
+    wait: Option<u64>,
+    boundary: &str,
+    data: Data
+) -> UploadResponse
+{
+    let db = &*conn;
+    let repo = get_repo_by_account_and_name(db, account.id, repo)?
         .ok_or(NotFound)?;
 
-    let (name, version, arch, compression) = parse_pkg_filename(&package)
+    let (name, version, arch, compression) = parse_pkg_filename(package)
         .map_err(|_| BadRequest("Package file name has invalid format.".into()))?;
-    let existing_package = get_package_by_repo(&*db, repo.id, &name, &version, &arch)?;
+    let existing_package = get_package_by_repo(db, repo.id, &name, &version, &arch)?;
     if existing_package.is_some() {
         info!("Aborting upload early, because package already exists in this version.");
         Err(Conflict)?
@@ -44,7 +110,7 @@ pub fn upload(db: Db, active_account: Account, account: String, repo: String, pa
 
     info!("Saving uploaded files to disk...");
     let ((package_file, package_size), (signature_file, signature_size)) =
-        save_uploaded_files(data, &boundary.0)?;
+        save_uploaded_files(data, boundary)?;
     info!("Received package of size {} and signature of size {}.",
           package_size, signature_size);
 
@@ -73,24 +139,56 @@ pub fn upload(db: Db, active_account: Account, account: String, repo: String, pa
         signature: signature_file,
         compression: compression,
         repo_id: repo.id,
+        description: pkginfo.get_single("pkgdesc").map(|d| d.to_owned()),
     };
 
     info!("Adding package to database: {:?}", package);
-    let package = create_package(&*db, &package)
+    let package = create_package(db, &package)
         .expect_conflict()?
         .ok_or(Conflict)?;
 
-    for depends in pkginfo.get("depend") {
-        create_package_depends(&*db, package.id, depends.into())?;
+    for kind in DependencyKind::ALL {
+        for depends in pkginfo.get(&kind.to_string()) {
+            create_package_depends(db, package.id, depends.into(), *kind)?;
+        }
     }
 
-    create_package_provides(&*db, package.id, package.name.clone())?;
+    create_package_provides(db, package.id, format!("{}={}", package.name, package.version))?;
 
     for provides in pkginfo.get("provides") {
-        create_package_provides(&*db, package.id, provides.into())?;
+        create_package_provides(db, package.id, provides.into())?;
     }
 
-    create_repo_action(&*db, package.id, RepoActionOp::Add)?;
+    create_package_event(db, &NewPackageEvent::new(&package, PackageEventKind::Uploaded))?;
+    trigger_webhooks(db, repo.id, WebhookEvent::PackageUploaded,
+        serde_json::json!({ "package": package_data(&package) }))?;
+
+    let job = create_repo_action(db, package.id, RepoActionOp::Add)?;
+    // The connection is not needed while waiting for the activation.
+    drop(conn);
+    let job = wait_for_job_limited(pool, job.id, wait)?
+        .ok_or(NotFound)?;
+
+    let to_strings = |values: Vec<&str>| values.into_iter()
+        .map(|v| v.to_owned())
+        .collect::<Vec<_>>();
+    let uploaded_package = UploadedPackage {
+        id: package.id,
+        name: package.name,
+        version: package.version,
+        arch: package.arch,
+        size: package.size,
+        description: package.description,
+        url: pkginfo.get_single("url").map(|u| u.to_owned()),
+        licenses: to_strings(pkginfo.get("license")),
+        depends: to_strings(pkginfo.get("depend")),
+        optdepends: to_strings(pkginfo.get("optdepend")),
+        makedepends: to_strings(pkginfo.get("makedepend")),
+        checkdepends: to_strings(pkginfo.get("checkdepend")),
+        provides: to_strings(pkginfo.get("provides"))
+    };
+    let job = augment_job(&*pool.get()?, job)?;
+    UploadResponse { package: uploaded_package, job }
 }
 
 #[throws]
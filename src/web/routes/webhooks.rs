@@ -0,0 +1,135 @@
+use fehler::throws;
+use rocket::request::Form;
+use rocket::response::Redirect;
+use rocket_contrib::templates::Template;
+use serde::Serialize;
+
+use crate::db::create_webhook;
+use crate::db::delete_webhook;
+use crate::db::get_webhook_deliveries_by_repo;
+use crate::db::get_webhooks_by_repo;
+use crate::db::models::Account;
+use crate::db::models::NewWebhook;
+use crate::db::models::WebhookEvent;
+use crate::net::check_public_url;
+use crate::web::ctx_base::BaseContext;
+use crate::web::Error;
+use crate::web::Error::*;
+use crate::web::models::format_timestamp;
+use crate::web::props::Props;
+use crate::web::routes::create_random_token;
+use crate::web::routes::load_repo;
+use crate::web::routes::validate_access;
+
+/// How many deliveries are shown in the log.
+const DELIVERY_LOG_SIZE: i64 = 50;
+
+#[derive(Serialize)]
+struct WebhooksContext {
+    base: BaseContext,
+    account: String,
+    repo: String,
+    webhooks: Vec<Webhook>,
+    deliveries: Vec<Delivery>
+}
+
+#[derive(Serialize)]
+struct Webhook {
+    id: i32,
+    url: String,
+    secret: String,
+    created: String
+}
+
+#[derive(Serialize)]
+struct Delivery {
+    id: i32,
+    url: String,
+    event: WebhookEvent,
+    attempts: i32,
+    status_code: Option<i32>,
+    error: Option<String>,
+    created: String,
+    last_attempt: Option<String>,
+    delivered: Option<String>
+}
+
+#[throws]
+#[get("/<account>/<repo>/webhooks")]
+pub fn route_webhooks(props: Props, active_account: Account, account: String, repo: String) -> Template {
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+
+    let webhooks = get_webhooks_by_repo(&*props.db, repo.id)?.into_iter()
+        .map(|w| Webhook { id: w.id, url: w.url, secret: w.secret, created: format_timestamp(w.created) })
+        .collect();
+    let deliveries = get_webhook_deliveries_by_repo(&*props.db, repo.id, DELIVERY_LOG_SIZE)?.into_iter()
+        .map(|(d, w)| Delivery {
+            id: d.id,
+            url: w.url,
+            event: d.event,
+            attempts: d.attempts,
+            status_code: d.status_code,
+            error: d.error,
+            created: format_timestamp(d.created),
+            last_attempt: d.last_attempt.map(format_timestamp),
+            delivered: d.delivered.map(format_timestamp)
+        })
+        .collect();
+
+    let base = BaseContext::new(&props.account);
+    let context = WebhooksContext { base, account: account.name, repo: repo.name, webhooks, deliveries };
+    Template::render("webhooks", context)
+}
+
+#[derive(FromForm)]
+pub struct AddWebhook {
+    url: String
+}
+
+#[throws]
+#[post("/<account>/<repo>/webhooks", data = "<data>", rank = 4)]
+pub fn route_webhook_add(
+    props: Props,
+    active_account: Account,
+    account: String,
+    repo: String,
+    data: Form<AddWebhook>
+) -> Redirect
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+
+    let url = data.url.trim();
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| BadRequest(format!("Invalid URL {}: {}", url, e)))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        Err(BadRequest("Field 'url' must be an HTTP or HTTPS URL.".into()))?
+    }
+    check_public_url(&parsed)
+        .map_err(|e| BadRequest(e.to_string()))?;
+
+    create_webhook(&*props.db, &NewWebhook {
+        repo_id: repo.id,
+        url: url.to_owned(),
+        secret: create_random_token()
+    })?;
+
+    Redirect::to(format!("/{}/{}/webhooks", account.name, repo.name))
+}
+
+#[throws]
+#[delete("/<account>/<repo>/webhooks/<id>", rank = 2)]
+pub fn route_webhook_remove(
+    props: Props,
+    active_account: Account,
+    account: String,
+    repo: String,
+    id: i32
+) -> Redirect
+{
+    let account = validate_access(active_account, account)?;
+    let repo = load_repo(&*props.db, account.id, &repo)?;
+    delete_webhook(&*props.db, repo.id, id)?;
+    Redirect::to(format!("/{}/{}/webhooks", account.name, repo.name))
+}
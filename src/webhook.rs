@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use diesel::PgConnection;
+use fehler::throws;
+use hmac::Hmac;
+use hmac::Mac;
+use reqwest::Url;
+use serde_json::json;
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::db::create_webhook_delivery;
+use crate::db::get_account;
+use crate::db::get_repo;
+use crate::db::get_webhooks_by_repo;
+use crate::db::models::NewWebhookDelivery;
+use crate::db::models::Package;
+use crate::db::models::Webhook;
+use crate::db::models::WebhookDelivery;
+use crate::db::models::WebhookEvent;
+use crate::error::Error;
+use crate::format_pkg_filename;
+use crate::jobs::create_deliver_webhook;
+use crate::net::public_client;
+
+/// How often a delivery is attempted before its job fails.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Queues a delivery of the event to every webhook of the repository. The
+/// payload describes the event and the repository, and includes the fields of
+/// `data`, which has to be an object.
+#[throws]
+pub fn trigger_webhooks(conn: &PgConnection, repo_id: i32, event: WebhookEvent, data: Value) {
+    let webhooks = get_webhooks_by_repo(conn, repo_id)?;
+    if !webhooks.is_empty() {
+        let repo = get_repo(conn, repo_id)?;
+        let owner = get_account(conn, repo.owner_id)?;
+        let mut payload = json!({
+            "event": event,
+            "repo": { "id": repo.id, "name": repo.name, "owner": owner.name },
+            "created": Utc::now().naive_utc()
+        });
+        if let (Value::Object(payload), Value::Object(data)) = (&mut payload, data) {
+            payload.extend(data);
+        }
+
+        for webhook in webhooks {
+            let delivery = create_webhook_delivery(conn, &NewWebhookDelivery {
+                webhook_id: webhook.id,
+                event,
+                payload: payload.clone()
+            })?;
+            create_deliver_webhook(conn, repo_id, delivery.id)?;
+        }
+    }
+}
+
+/// How a package is described in payloads.
+pub fn package_data(package: &Package) -> Value {
+    json!({
+        "id": package.id,
+        "name": package.name,
+        "version": package.version,
+        "arch": package.arch,
+        "filename": format_pkg_filename(package)
+    })
+}
+
+/// Posts the payload of the delivery to the webhook and returns the status
+/// code of the response. The host of the webhook is resolved and checked for
+/// every delivery, since it may resolve to a different address than when the
+/// webhook was added, and the request goes to the checked address. Redirects
+/// are not followed.
+#[throws]
+pub fn deliver(webhook: &Webhook, delivery: &WebhookDelivery) -> u16 {
+    let url = Url::parse(&webhook.url)
+        .map_err(|e| format!("Invalid URL {}: {}", webhook.url, e))?;
+    let body = serde_json::to_vec(&delivery.payload)?;
+    let response = public_client(&url, Duration::from_secs(30))?
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("X-Paketbote-Event", delivery.event.to_string())
+        .header("X-Paketbote-Delivery", delivery.id.to_string())
+        .header("X-Paketbote-Signature", sign(&webhook.secret, &body))
+        .body(body)
+        .send()?;
+    response.status().as_u16()
+}
+
+/// Signs the body with the secret of the webhook, so that receivers can check
+/// that a payload comes from us: `sha256=` followed by the hex encoded
+/// HMAC-SHA256 of the body.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.input(body);
+    let code = mac.result().code();
+    let hex = code.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("sha256={}", hex)
+}
+
+/// How long to wait before the next attempt, after the given number of failed
+/// attempts: one minute, then four times as long after every further failure.
+/// Returns nothing once the attempts are used up.
+pub fn retry_delay(attempts: i32) -> Option<chrono::Duration> {
+    if attempts < 1 || attempts >= MAX_ATTEMPTS {
+        None
+    } else {
+        Some(chrono::Duration::minutes(4i64.pow(attempts as u32 - 1)))
+    }
+}
+
+#[test]
+fn test_sign() {
+    // Test case 2 of RFC 4231.
+    assert_eq!(sign("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+}
+
+#[test]
+fn test_retry_delay() {
+    assert_eq!(retry_delay(1), Some(chrono::Duration::minutes(1)));
+    assert_eq!(retry_delay(2), Some(chrono::Duration::minutes(4)));
+    assert_eq!(retry_delay(4), Some(chrono::Duration::minutes(64)));
+    assert_eq!(retry_delay(MAX_ATTEMPTS), None);
+}
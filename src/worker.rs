@@ -0,0 +1,87 @@
+use std::fs;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use diesel::PgConnection;
+use fehler::throws;
+
+use crate::connect_db;
+use crate::db::JOB_LEASE_SECONDS;
+use crate::db::release_jobs;
+use crate::db::renew_job_leases;
+use crate::error::Error;
+
+/// Records whether the worker has been asked to stop (by SIGTERM or SIGINT).
+/// The signal only sets a flag, so the worker can finish the job at hand and
+/// exit between two jobs instead of being killed in the middle of one.
+#[derive(Clone)]
+pub struct Shutdown(Arc<AtomicBool>);
+
+impl Shutdown {
+    #[throws]
+    pub fn install() -> Shutdown {
+        let flag = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(signal_hook::SIGTERM, Arc::clone(&flag))?;
+        signal_hook::flag::register(signal_hook::SIGINT, Arc::clone(&flag))?;
+        Shutdown(flag)
+    }
+
+    pub fn requested(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Sleeps for the given duration, but wakes up early if a shutdown is
+    /// requested in the meantime.
+    pub fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.requested() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::sleep((deadline - now).min(Duration::from_millis(250)));
+        }
+    }
+}
+
+/// Returns a name for this worker process that no other worker shares, which
+/// is recorded with the jobs it claims.
+pub fn worker_id() -> String {
+    let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_owned())
+        .unwrap_or_else(|_| "localhost".to_owned());
+    format!("{}:{}", hostname, process::id())
+}
+
+/// Renews the leases of the running jobs of the worker in the background, so
+/// that long jobs are not released while the worker is still alive. The
+/// thread ends with the process.
+#[throws]
+pub fn start_heartbeat(worker: &str) {
+    let conn = connect_db()?;
+    let worker = worker.to_owned();
+    let interval = Duration::from_secs(JOB_LEASE_SECONDS as u64 / 3);
+    thread::spawn(move || loop {
+        if let Err(error) = renew_job_leases(&conn, &worker) {
+            println!("Failed to renew job leases: {:?}", error);
+        }
+        thread::sleep(interval);
+    });
+}
+
+/// Puts the running jobs with the given tags whose lease has expired back into
+/// the queue. Those were claimed by a worker that died in the middle of them.
+#[throws]
+pub fn release_stale_jobs(conn: &PgConnection, tags: &[&str]) {
+    for tag in tags {
+        let released = release_jobs(conn, tag)?;
+        if released > 0 {
+            println!("Released {} stale {} jobs.", released, tag);
+        }
+    }
+}